@@ -0,0 +1,186 @@
+//! Typed input bindings, and layering the player's own bindings over the defaults
+use std::{
+    fmt,
+    path::Path
+};
+use amethyst::{
+    config::Config,
+    input::{BindingError, BindingTypes, Bindings, Button}
+};
+use serde::{Serialize, Deserialize};
+
+#[derive(Eq, PartialEq, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum AxisBinding {
+    Horizontal,
+    Vertical,
+    // raw stick values for a controller, see gamepad.rs
+    PadHorizontal(u32),
+    PadVertical(u32)
+}
+
+#[derive(Eq, PartialEq, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum ActionBinding {
+    Jump,
+    Attack,
+    Eat,
+    Interact,
+    Pause,
+    Climb,
+    Drop,
+    NextItem
+}
+
+impl ActionBinding {
+    // in the order they're listed on the rebinding screen
+    pub fn all() -> &'static [ActionBinding] {
+        &[
+            ActionBinding::Jump,
+            ActionBinding::Attack,
+            ActionBinding::Eat,
+            ActionBinding::Interact,
+            ActionBinding::Pause,
+            ActionBinding::Climb,
+            ActionBinding::Drop,
+            ActionBinding::NextItem,
+        ]
+    }
+}
+
+impl fmt::Display for ActionBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug)]
+pub struct GameBindings;
+
+impl BindingTypes for GameBindings {
+    type Axis = AxisBinding;
+    type Action = ActionBinding;
+}
+
+// loads the default bindings, then replaces any axis or action the player has rebound
+pub fn load_bindings(
+    defaults_path: &Path,
+    user_path: &Path
+) -> Result<Bindings<GameBindings>, amethyst::Error> {
+    let mut bindings = Bindings::<GameBindings>::load_no_fallback(defaults_path)?;
+
+    if user_path.exists() {
+        match Bindings::<GameBindings>::load_no_fallback(user_path) {
+            Ok(user_bindings) => overlay_bindings(&mut bindings, &user_bindings),
+            Err(e) => log::error!("Ignoring user bindings at {:?}: {}", user_path, e),
+        }
+    }
+
+    Ok(bindings)
+}
+
+pub fn overlay_bindings(bindings: &mut Bindings<GameBindings>, overrides: &Bindings<GameBindings>) {
+    for axis in overrides.axes() {
+        if let Some(axis_binding) = overrides.axis(axis) {
+            bindings.remove_axis(axis);
+            if let Err(e) = bindings.insert_axis(*axis, axis_binding.clone()) {
+                log::error!("Could not rebind {:?}: {:?}", axis, e);
+            }
+        }
+    }
+
+    for action in overrides.actions() {
+        let buttons = overrides
+            .action_bindings(action)
+            .map(|combo| combo.to_vec())
+            .collect::<Vec<Vec<Button>>>();
+        // a clash leaves the default in place rather than the action unbound
+        if let Err(e) = set_action_bindings(bindings, *action, buttons) {
+            log::error!("Could not rebind {:?}, keeping the default: {:?}", action, e);
+        }
+    }
+}
+
+// replaces every binding for an action, leaving the old ones alone if any of the new ones clash
+pub fn set_action_bindings(
+    bindings: &mut Bindings<GameBindings>,
+    action: ActionBinding,
+    buttons: Vec<Vec<Button>>
+) -> Result<(), BindingError<GameBindings>> {
+    set_bindings(bindings, vec![(action, buttons)])
+}
+
+// replaces the bindings for several actions at once, so they can swap buttons between them.
+// Either every change goes in or none of them do.
+pub fn set_bindings(
+    bindings: &mut Bindings<GameBindings>,
+    changes: Vec<(ActionBinding, Vec<Vec<Button>>)>
+) -> Result<(), BindingError<GameBindings>> {
+    let previous = changes
+        .iter()
+        .map(|(action, _)| (*action, combos(bindings, *action)))
+        .collect::<Vec<(ActionBinding, Vec<Vec<Button>>)>>();
+
+    replace_bindings(bindings, &previous, &changes).map_err(|e| {
+        // put back what was there, which can't clash since it was all bound before
+        let _ = replace_bindings(bindings, &changes, &previous);
+        e
+    })
+}
+
+fn replace_bindings(
+    bindings: &mut Bindings<GameBindings>,
+    old: &[(ActionBinding, Vec<Vec<Button>>)],
+    new: &[(ActionBinding, Vec<Vec<Button>>)]
+) -> Result<(), BindingError<GameBindings>> {
+    for (action, combos) in old {
+        for combo in combos {
+            // when putting things back some of these never got bound, which is fine
+            let _ = bindings.remove_action_binding(action, combo);
+        }
+    }
+    for (action, combos) in new {
+        for combo in combos {
+            bindings.insert_action_binding(*action, combo.clone())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn combos(bindings: &Bindings<GameBindings>, action: ActionBinding) -> Vec<Vec<Button>> {
+    bindings
+        .action_bindings(&action)
+        .map(|combo| combo.to_vec())
+        .collect()
+}
+
+// the action a combo is already bound to, if any
+pub fn action_bound_to(bindings: &Bindings<GameBindings>, combo: &[Button]) -> Option<ActionBinding> {
+    ActionBinding::all()
+        .iter()
+        .find(|action| bindings.action_bindings(*action).any(|bound| bound == combo))
+        .cloned()
+}
+
+// human readable list of what an action is bound to, e.g. "Space / X"
+pub fn describe_action(bindings: &Bindings<GameBindings>, action: ActionBinding) -> String {
+    let combos = bindings
+        .action_bindings(&action)
+        .map(|combo| combo
+            .iter()
+            .map(|button| describe_button(*button))
+            .collect::<Vec<String>>()
+            .join("+"))
+        .collect::<Vec<String>>();
+
+    if combos.is_empty() {
+        "unbound".to_string()
+    } else {
+        combos.join(" / ")
+    }
+}
+
+fn describe_button(button: Button) -> String {
+    match button {
+        Button::Key(key) => format!("{:?}", key),
+        other => format!("{:?}", other),
+    }
+}
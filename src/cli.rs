@@ -0,0 +1,276 @@
+//! Parsing the game binary's command-line arguments
+use std::{
+    error::Error,
+    fmt,
+    path::PathBuf,
+    str::FromStr
+};
+use log::LevelFilter;
+
+pub const DEFAULT_FRAME_LIMIT: u32 = 60;
+
+pub const USAGE: &str = "\
+Usage: ld45 [OPTIONS]
+
+Options:
+    --level <FILE>          level descriptor (.ron) or TMX map to start in
+    --frame-limit <FPS>     frames per second to cap rendering at (default 60, or the options menu setting)
+    --window-size <WxH>     override the window size from display.ron and the options menu, e.g. 1280x640
+    --config-dir <DIR>      directory holding display.ron, input.ron and the other configs
+    --log-level <LEVEL>     off, error, warn, info, debug or trace (default info)
+    --record <FILE>         record this session's input to a file
+    --replay <FILE>         play back a recorded session
+    --headless              run the level without a window and log the end state
+    --ticks <N>             how many ticks a headless run lasts
+    --validate-assets       check every config, map, sprite sheet and prefab, then exit
+    -h, --help              print this message";
+
+#[derive(Debug)]
+pub enum ArgsError {
+    UnknownArgument(String),
+    MissingValue(String),
+    InvalidValue {
+        argument: String,
+        value: String,
+        expected: &'static str,
+    },
+    Conflict(&'static str, &'static str),
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgsError::UnknownArgument(argument) => write!(f, "unknown argument '{}'", argument),
+            ArgsError::MissingValue(argument) => write!(f, "'{}' needs a value", argument),
+            ArgsError::InvalidValue { argument, value, expected } => {
+                write!(f, "invalid value '{}' for '{}', expected {}", value, argument, expected)
+            },
+            ArgsError::Conflict(first, second) => write!(f, "'{}' can't be used with '{}'", first, second),
+        }
+    }
+}
+
+impl Error for ArgsError {}
+
+#[derive(Debug)]
+pub struct Args {
+    pub level: Option<PathBuf>,
+    pub frame_limit: Option<u32>,
+    pub window_size: Option<(u32, u32)>,
+    pub config_dir: Option<PathBuf>,
+    pub log_level: LevelFilter,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    pub ticks: Option<usize>,
+    pub validate_assets: bool,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            level: None,
+            frame_limit: None,
+            window_size: None,
+            config_dir: None,
+            log_level: LevelFilter::Info,
+            record: None,
+            replay: None,
+            headless: false,
+            ticks: None,
+            validate_assets: false,
+            help: false,
+        }
+    }
+}
+
+impl Args {
+    /// Parses the arguments after the program name, accepting both `--flag value` and `--flag=value`
+    pub fn parse<I: IntoIterator<Item = String>>(arguments: I) -> Result<Self, ArgsError> {
+        let mut args = Args::default();
+        let mut arguments = arguments.into_iter();
+
+        while let Some(argument) = arguments.next() {
+            let (flag, inline_value) = match argument.find('=') {
+                Some(index) if argument.starts_with("--") => {
+                    (argument[..index].to_string(), Some(argument[index + 1..].to_string()))
+                },
+                _ => (argument.clone(), None)
+            };
+
+            // takes the value for the current flag, wherever it was written
+            let mut value = || inline_value
+                .clone()
+                .or_else(|| arguments.next())
+                .ok_or_else(|| ArgsError::MissingValue(flag.clone()));
+
+            match flag.as_str() {
+                "--level" => args.level = Some(PathBuf::from(value()?)),
+                "--frame-limit" => args.frame_limit = Some(parse_value(&flag, value()?, "a whole number of frames")?),
+                "--window-size" => args.window_size = Some(parse_window_size(&flag, value()?)?),
+                "--config-dir" => args.config_dir = Some(PathBuf::from(value()?)),
+                "--log-level" => args.log_level = parse_value(&flag, value()?, "off, error, warn, info, debug or trace")?,
+                "--record" => args.record = Some(PathBuf::from(value()?)),
+                "--replay" => args.replay = Some(PathBuf::from(value()?)),
+                "--ticks" => args.ticks = Some(parse_value(&flag, value()?, "a whole number of ticks")?),
+                "--headless" if inline_value.is_none() => args.headless = true,
+                "--validate-assets" if inline_value.is_none() => args.validate_assets = true,
+                "-h" | "--help" if inline_value.is_none() => args.help = true,
+                _ => return Err(ArgsError::UnknownArgument(argument)),
+            }
+        }
+
+        if args.record.is_some() && args.replay.is_some() {
+            return Err(ArgsError::Conflict("--record", "--replay"));
+        }
+        // headless runs are fed by a script, there's no player input to record
+        if args.record.is_some() && args.headless {
+            return Err(ArgsError::Conflict("--record", "--headless"));
+        }
+        if args.frame_limit == Some(0) {
+            return Err(ArgsError::InvalidValue {
+                argument: "--frame-limit".to_string(),
+                value: "0".to_string(),
+                expected: "at least 1 frame",
+            });
+        }
+
+        Ok(args)
+    }
+}
+
+fn parse_value<T: FromStr>(argument: &str, value: String, expected: &'static str) -> Result<T, ArgsError> {
+    value.parse().map_err(|_| ArgsError::InvalidValue {
+        argument: argument.to_string(),
+        value,
+        expected,
+    })
+}
+
+fn parse_window_size(argument: &str, value: String) -> Result<(u32, u32), ArgsError> {
+    let invalid = || ArgsError::InvalidValue {
+        argument: argument.to_string(),
+        value: value.clone(),
+        expected: "WIDTHxHEIGHT, e.g. 1280x640",
+    };
+
+    let mut dimensions = value.split('x').map(|dimension| dimension.parse::<u32>());
+    match (dimensions.next(), dimensions.next(), dimensions.next()) {
+        (Some(Ok(width)), Some(Ok(height)), None) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arguments: &[&str]) -> Result<Args, ArgsError> {
+        Args::parse(arguments.iter().map(|argument| (*argument).to_string()))
+    }
+
+    #[test]
+    fn no_arguments_gives_the_defaults() {
+        let args = parse(&[]).unwrap();
+        assert!(args.level.is_none() && args.frame_limit.is_none() && args.window_size.is_none());
+        assert!(args.config_dir.is_none() && args.record.is_none() && args.replay.is_none() && args.ticks.is_none());
+        assert_eq!(args.log_level, LevelFilter::Info);
+        assert!(!args.headless && !args.validate_assets && !args.help);
+    }
+
+    #[test]
+    fn every_flag_is_read() {
+        let args = parse(&[
+            "--level", "level2.tmx",
+            "--frame-limit", "30",
+            "--window-size", "1280x640",
+            "--config-dir", "configs",
+            "--log-level", "debug",
+            "--replay", "run.ron",
+            "--headless",
+            "--ticks", "600",
+            "--validate-assets",
+            "--help",
+        ]).unwrap();
+        assert_eq!(args.level, Some(PathBuf::from("level2.tmx")));
+        assert_eq!(args.frame_limit, Some(30));
+        assert_eq!(args.window_size, Some((1280, 640)));
+        assert_eq!(args.config_dir, Some(PathBuf::from("configs")));
+        assert_eq!(args.log_level, LevelFilter::Debug);
+        assert_eq!(args.replay, Some(PathBuf::from("run.ron")));
+        assert!(args.headless);
+        assert_eq!(args.ticks, Some(600));
+        assert!(args.validate_assets);
+        assert!(args.help);
+
+        assert_eq!(parse(&["--record", "run.ron"]).unwrap().record, Some(PathBuf::from("run.ron")));
+        assert!(parse(&["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn values_can_follow_an_equals_sign() {
+        let args = parse(&["--frame-limit=144", "--level=level1.ron"]).unwrap();
+        assert_eq!(args.frame_limit, Some(144));
+        assert_eq!(args.level, Some(PathBuf::from("level1.ron")));
+    }
+
+    #[test]
+    fn unknown_flags_are_rejected() {
+        match parse(&["--fullscreen"]) {
+            Err(ArgsError::UnknownArgument(argument)) => assert_eq!(argument, "--fullscreen"),
+            other => panic!("Expected an unknown argument error, got {:?}", other),
+        }
+        // switches don't take values
+        match parse(&["--headless=yes"]) {
+            Err(ArgsError::UnknownArgument(argument)) => assert_eq!(argument, "--headless=yes"),
+            other => panic!("Expected an unknown argument error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn flags_without_their_value_are_rejected() {
+        for flag in &["--level", "--frame-limit", "--window-size", "--config-dir", "--log-level", "--record", "--replay", "--ticks"] {
+            match parse(&[*flag]) {
+                Err(ArgsError::MissingValue(argument)) => assert_eq!(argument, *flag),
+                other => panic!("Expected a missing value error for {}, got {:?}", flag, other),
+            }
+        }
+    }
+
+    #[test]
+    fn values_of_the_wrong_kind_are_rejected() {
+        let invalid = [
+            ("--frame-limit", "fast"),
+            ("--frame-limit", "-5"),
+            ("--frame-limit", "0"),
+            ("--ticks", "ten"),
+            ("--log-level", "loud"),
+            ("--window-size", "1280"),
+            ("--window-size", "1280xabc"),
+            ("--window-size", "0x640"),
+            ("--window-size", "1280x640x2"),
+        ];
+        for (flag, value) in invalid.iter() {
+            match parse(&[*flag, *value]) {
+                Err(ArgsError::InvalidValue { argument, value: bad_value, .. }) => {
+                    assert_eq!(argument, *flag);
+                    assert_eq!(bad_value, *value);
+                },
+                other => panic!("Expected {} {} to be invalid, got {:?}", flag, value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn conflicting_flags_are_rejected() {
+        match parse(&["--record", "a.ron", "--replay", "b.ron"]) {
+            Err(ArgsError::Conflict(first, second)) => assert_eq!((first, second), ("--record", "--replay")),
+            other => panic!("Expected a conflict, got {:?}", other),
+        }
+        match parse(&["--headless", "--record", "a.ron"]) {
+            Err(ArgsError::Conflict(first, second)) => assert_eq!((first, second), ("--record", "--headless")),
+            other => panic!("Expected a conflict, got {:?}", other),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use amethyst::{
     core::{
-        math::{Vector3, Vector2},
+        math::Vector2,
         timing::Time,
         transform::Transform
     },
@@ -9,7 +9,8 @@ use amethyst::{
 use nalgebra as na;
 use ncollide2d as nc;
 use crate::{
-    components
+    components,
+    load_error::LevelLoadError
 };
 
 pub type NcCollisionWorld = nc::pipeline::world::CollisionWorld<f32, Entity>;
@@ -19,6 +20,7 @@ pub const WALL_COLLISION_GROUP: usize = 1;
 pub const FOOD_COLLISION_GROUP: usize = 2;
 pub const EXIT_COLLISION_GROUP: usize = 3;
 pub const ENEMY_COLLISION_GROUP: usize = 4;
+pub const CHECKPOINT_COLLISION_GROUP: usize = 5;
 
 pub struct CollisionWorld {
     pub world: NcCollisionWorld
@@ -40,17 +42,33 @@ impl CollisionWorld {
 
         let ncollide_world = &mut self.world;
 
-        let mut collision_obj = ncollide_world
+        let collision_obj = ncollide_world
             .get_mut(collider.slab_handle)
             .expect("Invalid collision object");
 
-        let mut new_position = collision_obj.position().clone();
+        let mut new_position = *collision_obj.position();
         new_position.append_translation_mut(&na::Translation::from(distance));
         collision_obj.set_position(new_position);
 
         transform.set_translation_xyz(new_position.translation.x, new_position.translation.y, 0.0);
     }
 
+    // moves an entity straight to a position, e.g. when respawning
+    pub fn teleport(
+        &mut self,
+        transform: &mut Transform,
+        collider: &components::Collider,
+        position: Vector2<f32>
+    ) {
+        let collision_obj = self.world
+            .get_mut(collider.slab_handle)
+            .expect("Invalid collision object");
+
+        collision_obj.set_position(na::Isometry2::new(position, na::zero()));
+
+        transform.set_translation_xyz(position.x, position.y, 0.0);
+    }
+
     pub fn add_collision(
         &mut self,
         world: &mut World,
@@ -58,7 +76,28 @@ impl CollisionWorld {
         collision_group: usize,
         entity: Entity,
         transform: Transform
-    ) {
+    ) -> Result<(), LevelLoadError> {
+        let collider = self.add_object(half_extents, collision_group, entity, &transform);
+
+        world
+            .write_storage()
+            .insert(entity, collider)?;
+
+        world
+            .write_storage()
+            .insert(entity, transform)?;
+
+        Ok(())
+    }
+
+    // adds the collision object without touching any storages, for use inside systems
+    pub fn add_object(
+        &mut self,
+        half_extents: (f32, f32),
+        collision_group: usize,
+        entity: Entity,
+        transform: &Transform
+    ) -> components::Collider {
         let shape = nc::shape::Cuboid::new(Vector2::new(half_extents.0, half_extents.1));
         let mut entity_collide_group = nc::pipeline::object::CollisionGroups::new();
         entity_collide_group.set_membership(&[collision_group]);
@@ -69,25 +108,19 @@ impl CollisionWorld {
                 transform.translation().x,
                 transform.translation().y
             ), na::zero()),
-            nc::shape::ShapeHandle::new(shape.clone()),
+            nc::shape::ShapeHandle::new(shape),
             entity_collide_group,
             query_type,
             entity
         );
 
-        let collider = components::Collider {
+        components::Collider {
             slab_handle: entity_handle
-        };
-
-        world
-            .write_storage()
-            .insert(entity, collider)
-            .expect("Failed to add collider to entity");
+        }
+    }
 
-        world
-            .write_storage()
-            .insert(entity, transform)
-            .expect("Failed to add transform to entity");
+    pub fn remove(&mut self, collider: &components::Collider) {
+        self.world.remove(&[collider.slab_handle]);
     }
 
     pub fn update(&mut self) {
@@ -1,11 +1,19 @@
 use amethyst::{
     core::math::Vector2,
-    ecs::prelude::{Component, VecStorage, DenseVecStorage}
+    ecs::prelude::{Component, Entity, NullStorage, VecStorage, DenseVecStorage}
 };
 use ncollide2d as nc;
+use serde::{Serialize, Deserialize};
 
 pub struct HP {
-    pub value: u32
+    pub value: u32,
+    pub max: u32
+}
+
+impl HP {
+    pub fn heal(&mut self, amount: u32) {
+        self.value = (self.value + amount).min(self.max);
+    }
 }
 
 impl Component for HP {
@@ -26,18 +34,52 @@ pub enum PlayerState {
     Walking,
     Attacking,
     Climbing,
-    Crouching
+    Crouching,
+    Dying
 }
 
 pub struct Player {
     pub snapback: Vector2<f32>,
     pub lr_input_state: f32,
+    // 1.0 when facing right, -1.0 when facing left
+    pub facing: f32,
     pub state: PlayerState,
     pub belly: u8,
+    pub belly_max: u8,
+    // seconds of digestion since the belly last went down
+    pub digestion: f32,
+    pub starving_timer: f32,
+    // where we go back to if we die before touching any checkpoint
+    pub spawn_point: Vector2<f32>,
+    pub last_checkpoint: Option<Entity>,
+    pub dying_timer: f32,
+    // which swing of an attack chain we're on, 0 when not attacking
+    pub attack_chain: u8,
+    pub attack_timer: f32,
 }
 
 impl Player {
-    pub fn belly_max(&self) -> u8 { 10 }
+    pub fn new(spawn_point: Vector2<f32>, belly_max: u8) -> Self {
+        Player {
+            snapback: Vector2::new(0.0, 0.0),
+            lr_input_state: 0.0,
+            facing: 1.0,
+            state: PlayerState::Idle,
+            belly: belly_max / 2,
+            belly_max,
+            digestion: 0.0,
+            starving_timer: 0.0,
+            spawn_point,
+            last_checkpoint: None,
+            dying_timer: 0.0,
+            attack_chain: 0,
+            attack_timer: 0.0,
+        }
+    }
+
+    pub fn belly_max(&self) -> u8 { self.belly_max }
+
+    pub fn is_hungry(&self) -> bool { self.belly == 0 }
 }
 
 impl Component for Player {
@@ -62,6 +104,7 @@ impl Component for Motion {
     type Storage = VecStorage<Self>;
 }
 
+#[derive(Eq, PartialEq, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum FoodType {
     Carrot,
     Apple,
@@ -69,22 +112,41 @@ pub enum FoodType {
     Clover
 }
 
+impl FoodType {
+    // index into textures/objects.ron
+    pub fn sprite_number(self) -> usize {
+        match self {
+            FoodType::Carrot => 1,
+            FoodType::Apple => 2,
+            FoodType::Blueberries => 3,
+            FoodType::Clover => 4,
+        }
+    }
+
+    // as written in the level data, e.g. "Blueberries"
+    pub fn from_name(name: &str) -> Option<FoodType> {
+        match name {
+            "Carrot" => Some(FoodType::Carrot),
+            "Apple" => Some(FoodType::Apple),
+            "Blueberries" => Some(FoodType::Blueberries),
+            "Clover" => Some(FoodType::Clover),
+            _ => None
+        }
+    }
+}
+
+// what each food actually does lives in the food table, see food.rs
 pub struct Food {
-    fillingness: u8,
+    food_type: FoodType,
 }
 
 impl Food {
     pub fn new(food_type: FoodType) -> Self {
-        match food_type {
-            FoodType::Carrot => Food { fillingness: 3 },
-            FoodType::Apple => Food { fillingness: 6 },
-            FoodType::Blueberries => Food { fillingness: 1 },
-            FoodType::Clover => Food { fillingness: 0 },
-        }
+        Food { food_type }
     }
 
-    pub fn fillingness(&self) -> u8 {
-        self.fillingness
+    pub fn food_type(&self) -> FoodType {
+        self.food_type
     }
 }
 
@@ -92,8 +154,272 @@ impl Component for Food {
     type Storage = VecStorage<Self>;
 }
 
+pub struct Inventory {
+    slots: Vec<Option<FoodType>>,
+    selected: usize,
+}
+
+impl Inventory {
+    pub fn new(capacity: usize) -> Self {
+        Inventory {
+            slots: vec![None; capacity],
+            selected: 0,
+        }
+    }
+
+    // puts back what was in a saved game, keeping to this inventory's size
+    pub fn restore(&mut self, slots: &[Option<FoodType>], selected: usize) {
+        for (slot, saved) in self.slots.iter_mut().zip(slots.iter().chain(std::iter::repeat(&None))) {
+            *slot = *saved;
+        }
+        self.selected = if selected < self.slots.len() { selected } else { 0 };
+    }
+
+    // puts the food in the first free slot, returns false if we're out of room
+    pub fn add(&mut self, food_type: FoodType) -> bool {
+        match self.slots.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(food_type);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn selected(&self) -> Option<FoodType> {
+        self.slots.get(self.selected).and_then(|slot| *slot)
+    }
+
+    pub fn take_selected(&mut self) -> Option<FoodType> {
+        self.slots.get_mut(self.selected).and_then(|slot| slot.take())
+    }
+
+    pub fn put_back(&mut self, food_type: FoodType) {
+        if let Some(slot) = self.slots.get_mut(self.selected) {
+            *slot = Some(food_type);
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.slots.is_empty() {
+            self.selected = (self.selected + 1) % self.slots.len();
+        }
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn slots(&self) -> &[Option<FoodType>] {
+        &self.slots
+    }
+}
+
+impl Component for Inventory {
+    type Storage = DenseVecStorage<Self>;
+}
+
 pub struct Exit;
 
 impl Component for Exit {
     type Storage = VecStorage<Self>;
 }
+
+pub struct Enemy {
+    pub damage: u32,
+}
+
+impl Component for Enemy {
+    type Storage = VecStorage<Self>;
+}
+
+pub struct Checkpoint {
+    // object id from the level data
+    pub id: u32,
+    // whether the player has touched it yet
+    pub reached: bool,
+}
+
+impl Component for Checkpoint {
+    type Storage = VecStorage<Self>;
+}
+
+// food placed in the level data, so saves can tell which ones have been picked up
+pub struct Pickup {
+    // object id from the level data
+    pub id: u32,
+}
+
+impl Component for Pickup {
+    type Storage = VecStorage<Self>;
+}
+
+// the camera that tracks the player, where it's trying to get to and how far it's looking ahead.
+// position is before any camera effects, so shakes and pans don't feed back into following
+pub struct CameraFollow {
+    pub focus: Vector2<f32>,
+    pub look_ahead: f32,
+    pub position: Vector2<f32>,
+}
+
+impl Component for CameraFollow {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// a background piece that lags behind the camera. origin is where it sits when the camera is at 0, 0
+pub struct Parallax {
+    pub scroll: Vector2<f32>,
+    pub origin: Vector2<f32>,
+}
+
+impl Component for Parallax {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// anything built from the tile map file, so it can be torn down and rebuilt when the map changes
+#[derive(Default)]
+pub struct MapPart;
+
+impl Component for MapPart {
+    type Storage = NullStorage<Self>;
+}
+
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Stat {
+    Speed,
+    Power,
+    DamageTaken,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Modifier {
+    Add(f32),
+    Multiply(f32),
+}
+
+// something that happens every time a status effect ticks
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum TickEffect {
+    Heal(u32),
+    Damage(u32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tick {
+    pub interval: f32,
+    pub effect: TickEffect,
+    #[serde(skip)]
+    timer: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusEffect {
+    // effects with the same name stack with each other
+    pub name: String,
+    pub duration: f32,
+    pub max_stacks: u32,
+    pub modifiers: Vec<(Stat, Modifier)>,
+    pub tick: Option<Tick>,
+    #[serde(skip)]
+    elapsed: f32,
+}
+
+impl StatusEffect {
+    pub fn remaining(&self) -> f32 {
+        self.duration - self.elapsed
+    }
+}
+
+impl Default for StatusEffect {
+    fn default() -> Self {
+        StatusEffect {
+            name: String::new(),
+            duration: 0.0,
+            max_stacks: 1,
+            modifiers: Vec::new(),
+            tick: None,
+            elapsed: 0.0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    // once an effect is at its stack limit, adding it again refreshes the oldest stack
+    pub fn add(&mut self, effect: StatusEffect) {
+        let stacks = self.stacks(&effect.name);
+        if stacks < effect.max_stacks as usize {
+            self.effects.push(effect);
+        } else if let Some(oldest) = self.effects
+            .iter_mut()
+            .filter(|existing| existing.name == effect.name)
+            .max_by(|a, b| a.elapsed.partial_cmp(&b.elapsed).unwrap())
+        {
+            *oldest = effect;
+        }
+    }
+
+    pub fn stacks(&self, name: &str) -> usize {
+        self.effects.iter().filter(|effect| effect.name == name).count()
+    }
+
+    pub fn effects(&self) -> &[StatusEffect] {
+        &self.effects
+    }
+
+    // base value with every active modifier applied, additions first
+    pub fn effective(&self, stat: Stat, base: f32) -> f32 {
+        let modifiers = self.effects
+            .iter()
+            .flat_map(|effect| effect.modifiers.iter())
+            .filter(|(modified_stat, _)| *modified_stat == stat)
+            .map(|(_, modifier)| modifier);
+
+        let (added, multiplier) = modifiers.fold((0.0, 1.0), |(added, multiplier), modifier| {
+            match modifier {
+                Modifier::Add(amount) => (added + amount, multiplier),
+                Modifier::Multiply(factor) => (added, multiplier * factor),
+            }
+        });
+
+        ((base + added) * multiplier).max(0.0)
+    }
+
+    pub fn effective_power(&self, power: &Power) -> u32 {
+        self.effective(Stat::Power, power.value as f32).round() as u32
+    }
+
+    pub fn damage_taken(&self, damage: u32) -> u32 {
+        self.effective(Stat::DamageTaken, damage as f32).round() as u32
+    }
+
+    // advances every effect, dropping the expired ones, and returns the tick effects that fired
+    pub fn tick(&mut self, delta_seconds: f32) -> Vec<TickEffect> {
+        let mut fired = Vec::new();
+        for effect in self.effects.iter_mut() {
+            effect.elapsed += delta_seconds;
+            if let Some(tick) = effect.tick.as_mut() {
+                tick.timer += delta_seconds;
+                while tick.interval > 0.0 && tick.timer >= tick.interval {
+                    tick.timer -= tick.interval;
+                    fired.push(tick.effect);
+                }
+            }
+        }
+        self.effects.retain(|effect| effect.remaining() > 0.0);
+        fired
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+}
+
+impl Component for StatusEffects {
+    type Storage = DenseVecStorage<Self>;
+}
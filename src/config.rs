@@ -0,0 +1,97 @@
+//! Gameplay tuning loaded from the RON files in `resources/`
+use serde::{Serialize, Deserialize};
+
+/// How a player stat is adjusted when respawning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum StatRule {
+    Keep,
+    Reset(u32),
+    Penalise(u32),
+}
+
+impl StatRule {
+    pub fn apply(&self, value: u32) -> u32 {
+        match self {
+            StatRule::Keep => value,
+            StatRule::Reset(new_value) => *new_value,
+            StatRule::Penalise(amount) => value.saturating_sub(*amount),
+        }
+    }
+}
+
+impl Default for StatRule {
+    fn default() -> Self {
+        StatRule::Keep
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeathConfig {
+    pub lives: u32,
+    // seconds spent in the dying state before respawning
+    pub dying_duration: f32,
+    pub respawn_hp: u32,
+    pub belly_rule: StatRule,
+    pub power_rule: StatRule,
+}
+
+impl Default for DeathConfig {
+    fn default() -> Self {
+        DeathConfig {
+            lives: 3,
+            dying_duration: 1.5,
+            respawn_hp: 30,
+            belly_rule: StatRule::Keep,
+            power_rule: StatRule::Keep,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestionConfig {
+    pub belly_max: u8,
+    // seconds it takes to digest one point of belly while standing still
+    pub seconds_per_belly: f32,
+    // how much faster we digest while moving
+    pub exertion_multiplier: f32,
+    // fraction of normal speed when the belly is empty
+    pub hungry_speed_factor: f32,
+    pub starving_hp_drain: u32,
+    pub starving_drain_interval: f32,
+}
+
+impl Default for DigestionConfig {
+    fn default() -> Self {
+        DigestionConfig {
+            belly_max: 10,
+            seconds_per_belly: 8.0,
+            exertion_multiplier: 2.0,
+            hungry_speed_factor: 0.5,
+            starving_hp_drain: 1,
+            starving_drain_interval: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraConfig {
+    // half width and height of the box the player can move in without the camera following
+    pub deadzone: (f32, f32),
+    // how quickly the camera catches up, higher is snappier
+    pub smoothing: f32,
+    // how far ahead of the player the camera looks in the direction they're facing
+    pub look_ahead: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        CameraConfig {
+            deadzone: (64.0, 48.0),
+            smoothing: 5.0,
+            look_ahead: 96.0,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Data driven food effects, loaded from `resources/food.ron`
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::components::{
+    FoodType,
+    HP,
+    Player,
+    Power,
+    StatusEffect,
+    StatusEffects
+};
+
+// negative heal/power values are penalties
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodEffect {
+    pub fillingness: u8,
+    pub heal: i32,
+    pub power: i32,
+    pub effects: Vec<StatusEffect>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodTable {
+    pub foods: HashMap<FoodType, FoodEffect>,
+}
+
+impl FoodTable {
+    pub fn effect(&self, food_type: FoodType) -> FoodEffect {
+        self.foods.get(&food_type).cloned().unwrap_or_else(|| {
+            log::warn!("No entry for {:?} in the food table", food_type);
+            FoodEffect::default()
+        })
+    }
+}
+
+impl FoodEffect {
+    pub fn fits_in(&self, player: &Player) -> bool {
+        player.belly + self.fillingness <= player.belly_max()
+    }
+
+    pub fn apply(
+        &self,
+        player: &mut Player,
+        hp: &mut HP,
+        power: &mut Power,
+        status_effects: &mut StatusEffects
+    ) {
+        player.belly += self.fillingness;
+
+        if self.heal >= 0 {
+            hp.heal(self.heal as u32);
+        } else {
+            hp.value = hp.value.saturating_sub(self.heal.abs() as u32);
+        }
+
+        if self.power >= 0 {
+            power.value += self.power as u32;
+        } else {
+            power.value = power.value.saturating_sub(self.power.abs() as u32);
+        }
+
+        for effect in &self.effects {
+            status_effects.add(effect.clone());
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use amethyst::prelude::{GameData, State, StateData, StateEvent, Trans};
+use crate::{
+    menu::{Menu, MenuChoice, MenuItem},
+    systems::death::Lives
+};
+
+/// Shown in place of a level when it ends, either out of lives or through the exit
+#[derive(Default)]
+pub struct GameOver {
+    won: bool,
+    menu: Option<Menu>,
+}
+
+impl GameOver {
+    pub fn won() -> Self {
+        GameOver { won: true, menu: None }
+    }
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for GameOver {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        let StateData { world, .. } = data;
+
+        // either way the next run starts fresh
+        world.remove::<Lives>();
+
+        let title = if self.won { "You made it out!" } else { "Game Over" };
+        self.menu = Some(Menu::new(world, title, vec![
+            MenuItem::new("Back to title"),
+            MenuItem::new("Quit"),
+        ]));
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.delete(data.world);
+        }
+    }
+
+    fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        data.data.update(&data.world);
+
+        let choice = self.menu.as_mut().and_then(|menu| menu.update(data.world));
+        match choice {
+            // the level switched to this screen, so popping goes back to the title
+            Some(MenuChoice::Picked(0)) | Some(MenuChoice::Back) => Trans::Pop,
+            Some(MenuChoice::Picked(_)) => Trans::Quit,
+            Some(MenuChoice::Adjust(..)) | None => Trans::None,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Controller mapping profiles, loaded from `resources/controllers.ron`
+use std::collections::HashMap;
+use amethyst::input::{Axis, Bindings, Button, ControllerAxis, ControllerButton};
+use serde::{Serialize, Deserialize};
+use crate::bindings::{ActionBinding, AxisBinding, GameBindings};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    Linear,
+    Quadratic,
+    Cubic,
+    Exponent(f32),
+}
+
+impl ResponseCurve {
+    fn exponent(self) -> f32 {
+        match self {
+            ResponseCurve::Linear => 1.0,
+            ResponseCurve::Quadratic => 2.0,
+            ResponseCurve::Cubic => 3.0,
+            ResponseCurve::Exponent(exponent) => exponent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StickAxis {
+    pub axis: ControllerAxis,
+    #[serde(default)]
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerProfile {
+    pub name: String,
+    // which connected controller this profile is for, in connection order
+    pub controller_id: u32,
+    pub horizontal: StickAxis,
+    pub vertical: StickAxis,
+    pub deadzone: f32,
+    pub curve: ResponseCurve,
+    pub actions: HashMap<ActionBinding, ControllerButton>,
+}
+
+impl ControllerProfile {
+    // rescales so the stick goes from 0 at the edge of the deadzone to 1 at full tilt,
+    // then bends it by the response curve
+    pub fn process(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.deadzone {
+            return 0.0;
+        }
+
+        let scaled = ((magnitude - self.deadzone) / (1.0 - self.deadzone)).min(1.0);
+        scaled.powf(self.curve.exponent()) * value.signum()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControllerConfig {
+    pub profiles: Vec<ControllerProfile>,
+}
+
+impl ControllerConfig {
+    // adds each profile's sticks and buttons alongside the keyboard bindings
+    pub fn apply(&self, bindings: &mut Bindings<GameBindings>) {
+        for profile in &self.profiles {
+            let id = profile.controller_id;
+            let axes = [
+                (AxisBinding::PadHorizontal(id), &profile.horizontal),
+                (AxisBinding::PadVertical(id), &profile.vertical),
+            ];
+            for (axis_binding, stick) in axes.iter() {
+                // deadzones are handled by the profile so that the curve starts at the deadzone edge
+                let axis = Axis::Controller {
+                    controller_id: id,
+                    axis: stick.axis,
+                    invert: stick.invert,
+                    dead_zone: 0.0,
+                };
+                if let Err(e) = bindings.insert_axis(*axis_binding, axis) {
+                    log::error!("Could not bind {:?} for {}: {:?}", axis_binding, profile.name, e);
+                }
+            }
+
+            for (action, button) in &profile.actions {
+                if let Err(e) = bindings.insert_action_binding(*action, vec![Button::Controller(id, *button)]) {
+                    log::error!("Could not bind {:?} for {}: {:?}", action, profile.name, e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Running the gameplay systems without a window or renderer, for tests and CI
+use std::path::Path;
+use amethyst::{
+    config::Config,
+    core::{
+        SystemBundle,
+        timing::Time,
+        transform::TransformBundle
+    },
+    ecs::prelude::{DispatcherBuilder, World, WorldExt},
+    input::InputHandler,
+    prelude::{GameData, State, StateData},
+    StateEvent
+};
+use crate::{
+    bindings::{ActionBinding, GameBindings},
+    config::{DeathConfig, DigestionConfig},
+    food::FoodTable,
+    gamepad::ControllerConfig,
+    input_buffer::InputBufferConfig,
+    level1::Level1,
+    paths::GamePaths,
+    recording::{InputFrame, InputMode, Recording},
+    util::Rng
+};
+
+// fixed so that two runs of the same script always play out the same way
+const SIMULATION_SEED: u64 = 45;
+const TICK_SECONDS: f32 = 1.0 / 60.0;
+
+/// `ticks` frames of holding the given direction and actions, for building input scripts
+pub fn hold(horizontal: f32, actions: &[ActionBinding], ticks: usize) -> Vec<InputFrame> {
+    (0..ticks)
+        .map(|_| InputFrame {
+            delta_seconds: TICK_SECONDS,
+            horizontal,
+            vertical: 0.0,
+            actions: actions.to_vec(),
+        })
+        .collect()
+}
+
+/// A level running on the same systems as the game, fed by scripted input instead of a player
+pub struct Simulation<'a, 'b> {
+    world: World,
+    game_data: GameData<'a, 'b>,
+    level: Level1,
+}
+
+impl<'a, 'b> Simulation<'a, 'b> {
+    pub fn new(paths: &GamePaths, level_path: &Path, script: Vec<InputFrame>) -> amethyst::Result<Self> {
+        let mut world = World::new();
+
+        // only the bundles that don't need a window
+        let mut dispatcher_builder = DispatcherBuilder::new();
+        TransformBundle::new().build(&mut world, &mut dispatcher_builder)?;
+        let mut dispatcher = dispatcher_builder.build();
+        dispatcher.setup(&mut world);
+
+        world.insert(DeathConfig::load(paths.resource("death.ron")));
+        world.insert(DigestionConfig::load(paths.resource("digestion.ron")));
+        world.insert(FoodTable::load(paths.resource("food.ron")));
+        world.insert(InputBufferConfig::load(paths.resource("input_buffer.ron")));
+        // no controllers to read from, the script is the only input
+        world.insert(ControllerConfig::default());
+        world.insert(InputHandler::<GameBindings>::new());
+        world.insert(InputMode::replay(Recording {
+            seed: SIMULATION_SEED,
+            frames: script,
+        }));
+        world.insert(Rng::new(SIMULATION_SEED));
+        world.insert(paths.clone());
+
+        let mut game_data = GameData::new(dispatcher);
+        let mut level = Level1::headless(level_path.to_path_buf());
+        State::<GameData<'a, 'b>, StateEvent>::on_start(&mut level, StateData::new(&mut world, &mut game_data));
+        if let Some(e) = level.take_load_error() {
+            return Err(amethyst::Error::from_string(e.to_string()));
+        }
+
+        Ok(Simulation {
+            world,
+            game_data,
+            level,
+        })
+    }
+
+    pub fn run(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            // replay frames set their own delta, this is what's used once the script runs out
+            self.world.write_resource::<Time>().set_delta_seconds(TICK_SECONDS);
+            // transitions are left to the caller, a game over just shows up in `Lives`
+            let _ = State::<GameData<'a, 'b>, StateEvent>::update(
+                &mut self.level,
+                StateData::new(&mut self.world, &mut self.game_data)
+            );
+            self.world.maintain();
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    // for tests that need to set the level up a particular way before running it
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+}
@@ -0,0 +1,58 @@
+//! Noticing when files the current level was built from change on disk, for debug builds
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime
+};
+
+// checking modification times every frame is wasteful, and editors often save in several steps
+const POLL_INTERVAL: f32 = 0.5;
+
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    timer: f32,
+}
+
+impl FileWatcher {
+    /// Watches nothing in release builds, so levels never pay for polling there
+    pub fn new(files: Vec<PathBuf>) -> Option<Self> {
+        if cfg!(debug_assertions) {
+            Some(FileWatcher {
+                files: files
+                    .into_iter()
+                    .map(|path| {
+                        let modified = modified(&path);
+                        (path, modified)
+                    })
+                    .collect(),
+                timer: 0.0,
+            })
+        } else {
+            None
+        }
+    }
+
+    // true once per change, after the poll interval has passed
+    pub fn poll(&mut self, delta_seconds: f32) -> bool {
+        self.timer += delta_seconds;
+        if self.timer < POLL_INTERVAL {
+            return false;
+        }
+        self.timer = 0.0;
+
+        let mut changed = false;
+        for (path, last_modified) in &mut self.files {
+            let modified = modified(path);
+            if modified != *last_modified {
+                log::info!("{} changed on disk", path.display());
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
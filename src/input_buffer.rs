@@ -0,0 +1,123 @@
+//! Remembers recent presses for a short window so actions pressed slightly early aren't lost,
+//! and matches press sequences against combos
+use std::collections::{HashMap, VecDeque};
+use serde::{Serialize, Deserialize};
+use crate::bindings::ActionBinding;
+
+// presses older than this can't be part of any combo
+const HISTORY_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComboDefinition {
+    pub name: String,
+    pub sequence: Vec<ActionBinding>,
+    // most seconds allowed between two presses in the sequence
+    pub max_gap: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBufferConfig {
+    // seconds a press stays buffered, unless the action has its own window
+    pub default_window: f32,
+    pub windows: HashMap<ActionBinding, f32>,
+    pub combos: Vec<ComboDefinition>,
+}
+
+impl InputBufferConfig {
+    pub fn window(&self, action: ActionBinding) -> f32 {
+        self.windows.get(&action).cloned().unwrap_or(self.default_window)
+    }
+}
+
+impl Default for InputBufferConfig {
+    fn default() -> Self {
+        InputBufferConfig {
+            default_window: 0.15,
+            windows: HashMap::new(),
+            combos: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    action: ActionBinding,
+    // seconds since the buffer started
+    time: f32,
+}
+
+#[derive(Default)]
+pub struct InputBuffer {
+    clock: f32,
+    held: Vec<ActionBinding>,
+    buffered: Vec<Press>,
+    history: VecDeque<Press>,
+}
+
+impl InputBuffer {
+    // call once per tick with the actions currently held down
+    pub fn update(&mut self, delta_seconds: f32, held: &[ActionBinding], config: &InputBufferConfig) {
+        self.clock += delta_seconds;
+
+        let pressed: Vec<ActionBinding> = held
+            .iter()
+            .filter(|action| !self.held.contains(action))
+            .cloned()
+            .collect();
+        for action in pressed {
+            let press = Press { action, time: self.clock };
+            self.buffered.push(press);
+            self.history.push_back(press);
+            if self.history.len() > HISTORY_LENGTH {
+                self.history.pop_front();
+            }
+        }
+        self.held = held.to_vec();
+
+        let clock = self.clock;
+        self.buffered.retain(|press| clock - press.time <= config.window(press.action));
+    }
+
+    // true if the action was pressed within its window, and uses the press up
+    pub fn consume(&mut self, action: ActionBinding) -> bool {
+        match self.buffered.iter().position(|press| press.action == action) {
+            Some(index) => {
+                self.buffered.remove(index);
+                true
+            },
+            None => false
+        }
+    }
+
+    // the longest combo that ends with the most recent press, if any
+    pub fn matched_combo<'c>(&self, config: &'c InputBufferConfig) -> Option<&'c ComboDefinition> {
+        config.combos
+            .iter()
+            .filter(|combo| self.ends_with(combo))
+            .max_by_key(|combo| combo.sequence.len())
+    }
+
+    fn ends_with(&self, combo: &ComboDefinition) -> bool {
+        let length = combo.sequence.len();
+        if length == 0 || length > self.history.len() {
+            return false;
+        }
+
+        let recent = self.history.iter().skip(self.history.len() - length).collect::<Vec<&Press>>();
+        let actions_match = recent
+            .iter()
+            .zip(combo.sequence.iter())
+            .all(|(press, action)| press.action == *action);
+        let gaps_match = recent
+            .windows(2)
+            .all(|pair| pair[1].time - pair[0].time <= combo.max_gap);
+
+        actions_match && gaps_match
+    }
+
+    // starting a new chain shouldn't count presses from the last one
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
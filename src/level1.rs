@@ -1,54 +1,65 @@
+use std::path::{Path, PathBuf};
 use amethyst::{
-    animation::{
-        get_animation_set, AnimationBundle, AnimationCommand, AnimationControlSet, AnimationSet,
-        AnimationSetPrefab, EndControl,
-    },
-    assets::{AssetStorage, Loader, ProgressCounter, PrefabData, PrefabLoader, PrefabLoaderSystemDesc, RonFormat},
+    animation::AnimationSetPrefab,
+    assets::{AssetStorage, Loader, ProgressCounter, PrefabData, PrefabLoader, RonFormat},
     core::{
-        math::{Vector2, Vector3, Point3},
+        math::{Vector2, Vector3},
+        timing::Time,
         transform::Transform
     },
     derive::PrefabData,
     ecs::{
-        prelude::{Builder, Dispatcher, DispatcherBuilder, Entity, World, WorldExt}
+        prelude::{Builder, Dispatcher, DispatcherBuilder, Entity, Join, World, WorldExt}
     },
     error::Error,
+    input::{is_key_down, InputHandler},
     prelude::{GameData, State, StateData, StateEvent, Trans},
     renderer::{
-        camera::{Camera, Projection},
+        camera::Camera,
         formats::texture::ImageFormat,
         sprite::{
             prefab::{SpriteScenePrefab},
-            SpriteSheet, SpriteSheetHandle, SpriteRender},
+            Sprite, SpriteSheet, SpriteSheetHandle, SpriteRender},
         SpriteSheetFormat, Texture
     },
-    tiles::{Tile, TileMap as AmethystTileMap},
+    tiles::TileMap as AmethystTileMap,
     window::ScreenDimensions,
-    ui::{Anchor, TtfFormat, UiText, UiTransform},
+    ui::{Anchor, Stretch, UiCreator, UiImage, UiTransform},
+    winit::VirtualKeyCode,
 };
 use serde::{Serialize, Deserialize};
 
-use ncollide2d as nc;
-use nalgebra as na;
-
 use crate::{
     util::{
         PngPath,
         RonPath,
-        Exiting
+        Exiting,
+        png_dimensions
     },
     components,
     tile::TerrainTile,
-    tile_map::{TmxFilePath, create_map},
+    tile_map::{TileMap, TmxFilePath, create_map},
     systems,
     collision_world::*,
+    config::{DeathConfig, DigestionConfig},
+    systems::death::Lives,
     tile_map_collision,
-    level2
+    game_over::GameOver,
+    hot_reload::FileWatcher,
+    level_descriptor::{LevelDescriptor, ParallaxLayer},
+    load_error::{LevelLoadError, LoadErrorScreen},
+    menu::{PauseMenu, QuitToTitle},
+    paths::GamePaths,
+    bindings::{ActionBinding, GameBindings},
+    recording::InputMode,
+    rebind::RebindMenu,
+    save::{self, CurrentLevel, SaveGame}
 };
 
 #[derive(Eq, PartialOrd, PartialEq, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum AnimationId {
-    IdleRight
+    IdleRight,
+    Dying
 }
 
 #[derive(Debug, Clone, PrefabData, Deserialize)]
@@ -61,69 +72,96 @@ pub struct PlayerPrefabData {
 
 pub const SPRITE_WIDTH: f32 = 32.0;
 pub const HALF_WIDTH: f32 = SPRITE_WIDTH / 2.0;
-
-pub struct Level1<'a, 'b> {
-    dispatcher: Dispatcher<'a, 'b>
+pub const INVENTORY_SLOTS: usize = 3;
+// how long the screen takes to fade out after reaching the exit
+pub const EXIT_FADE_SECONDS: f32 = 0.5;
+
+// the dispatcher's systems are all 'static, which lets menus box a fresh level into a Trans
+pub struct Level1 {
+    dispatcher: Dispatcher<'static, 'static>,
+    // either a level descriptor or a bare map
+    level_path: PathBuf,
+    map_path: PathBuf,
+    // headless levels skip everything that needs a window: sprites, animation, camera and HUD
+    headless: bool,
+    load_error: Option<LevelLoadError>,
+    // only set in debug builds
+    watcher: Option<FileWatcher>,
+    // pause only opens on a fresh press, not when the key is still down from closing the menu
+    pause_held: bool,
+    exit_timer: f32,
+    // applied once the level has been built
+    save: Option<SaveGame>
 }
 
-impl<'a, 'b> Level1<'a, 'b> {
-    pub fn new() -> Self {
+impl Level1 {
+    pub fn new(level_path: PathBuf) -> Self {
         Level1 {
-            dispatcher: Level1::initialise_dispatcher()
+            dispatcher: Level1::initialise_dispatcher(false),
+            map_path: level_path.clone(),
+            level_path,
+            headless: false,
+            load_error: None,
+            watcher: None,
+            pause_held: false,
+            exit_timer: 0.0,
+            save: None
         }
     }
 
-    fn initialise_dispatcher() -> Dispatcher<'a, 'b> {
-        let mut dispatcher_builder = DispatcherBuilder::new();
-
-        dispatcher_builder.add(systems::motion::MotionSystem, "motion_system", &[]);
-        dispatcher_builder.add(systems::collision::CollisionSystem, "collision_system", &[]);
-        dispatcher_builder.add(systems::input::InputSystem, "input_system", &[]);
-        dispatcher_builder.add(systems::hud::HudSystem, "hud_system", &[]);
-        dispatcher_builder.add(systems::animation::AnimationSystem, "animation_system", &[]);
+    // builds the level without the food that was already picked up, then restores the player
+    pub fn from_save(level_path: PathBuf, save: SaveGame) -> Self {
+        Level1 {
+            save: Some(save),
+            ..Level1::new(level_path)
+        }
+    }
 
-        dispatcher_builder.build()
+    pub fn headless(level_path: PathBuf) -> Self {
+        Level1 {
+            dispatcher: Level1::initialise_dispatcher(true),
+            map_path: level_path.clone(),
+            level_path,
+            headless: true,
+            load_error: None,
+            watcher: None,
+            pause_held: false,
+            exit_timer: 0.0,
+            save: None
+        }
     }
-}
 
-impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level1<'a, 'b> {
-    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
-        let StateData { mut world, .. } = data;
+    pub fn take_load_error(&mut self) -> Option<LevelLoadError> {
+        self.load_error.take()
+    }
 
+    fn load(&mut self, mut world: &mut World) -> Result<(), LevelLoadError> {
         let mut collision_world = CollisionWorld::default();
+        let (descriptor, map_path) = LevelDescriptor::load_for(&self.level_path)?;
+        self.map_path = map_path;
 
-        self.dispatcher.setup(&mut world);
+        // only saves read pickups, so nothing else registers them
+        world.register::<components::Pickup>();
 
-        let (width, height) = {
-            let screen_dimensions = world.read_resource::<ScreenDimensions>();
-            (screen_dimensions.width(), screen_dimensions.height())
-        };
+        if !self.headless {
+            let (width, height) = {
+                let screen_dimensions = world.read_resource::<ScreenDimensions>();
+                (screen_dimensions.width(), screen_dimensions.height())
+            };
 
-        // Initialise the camera
-        initialise_camera(&mut world, Transform::from(Vector3::new(-HALF_WIDTH, HALF_WIDTH, 1.0)), Camera::standard_2d(width, height));
+            // Initialise the camera
+            initialise_camera(&mut world, Transform::from(Vector3::new(-HALF_WIDTH, HALF_WIDTH, 1.0)), Camera::standard_2d(width, height));
+        }
 
         // Initialise the tilemap
-        // panic with the given error message if we fail to load the tile map
-        let (amethyst_map, tile_map) = create_map::<TerrainTile>(
-            TmxFilePath(".\\resources\\level1.tmx"),
-            &mut world
-        ).unwrap_or_else(|e| panic!("Problem loading tile map: {:?}", e));
-
-
-        world
-            .create_entity()
-            .with(amethyst_map)
-            .with(Transform::default())
-            .build();
+        world.register::<components::MapPart>();
+        let (tile_map, amethyst_map) = self.load_map(&mut world)?;
+        spawn_tile_map(&mut world, amethyst_map);
+        if !self.headless {
+            spawn_parallax(&mut world, &descriptor, &tile_map)?;
+        }
 
         // Initialise the player
-        let player_prefab = world.exec(|loader: PrefabLoader<'_, PlayerPrefabData>| {
-            loader.load(
-                "prefab/player_animation.ron",
-                RonFormat,
-                ()
-            )
-        });
 
         /*let player_sheet_handle = load_sprite_sheet(
             &mut world,
@@ -134,17 +172,18 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level1<'a, 'b> {
         let mut player_transform = Transform::default();
         player_transform.set_translation_xyz(SPRITE_WIDTH * -12.0, SPRITE_WIDTH * -6.0, 0.0);
 
+        let belly_max = world.read_resource::<DigestionConfig>().belly_max;
+
         let player = world
             .create_entity()
-            .with(player_prefab)
-            .with(components::Player {
-                lr_input_state: 0.0,
-                snapback: Vector2::new(0.0, 0.0),
-                state: components::PlayerState::Idle,
-                belly: 0,
-            })
-            .with(components::HP { value: 30 })
+            .with(components::Player::new(
+                Vector2::new(player_transform.translation().x, player_transform.translation().y),
+                belly_max
+            ))
+            .with(components::HP { value: 30, max: 30 })
             .with(components::Power { value: 1 })
+            .with(components::StatusEffects::default())
+            .with(components::Inventory::new(INVENTORY_SLOTS))
             /*.with(SpriteRender {
                 sprite_sheet: player_sheet_handle.clone(),
                 sprite_number: 8
@@ -155,92 +194,49 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level1<'a, 'b> {
             })
             .build();
 
+        if !self.headless {
+            let player_prefab = world.exec(|loader: PrefabLoader<'_, PlayerPrefabData>| {
+                loader.load(
+                    "prefab/player_animation.ron",
+                    RonFormat,
+                    ()
+                )
+            });
+
+            world
+                .write_storage()
+                .insert(player, player_prefab)?;
+        }
+
         collision_world.add_collision(
             &mut world,
             (HALF_WIDTH, HALF_WIDTH),
             PLAYER_COLLISION_GROUP,
             player,
             player_transform
-        );
+        )?;
 
         // Initialise wall collision
-        let wall_transform = Transform::from(Vector3::new(SPRITE_WIDTH * 15.0, SPRITE_WIDTH * -6.0, 0.0));
-        let wall = world
-            .create_entity()
-            .build();
-
-        collision_world.add_collision(
-            world,
-            (HALF_WIDTH, HALF_WIDTH),
-            WALL_COLLISION_GROUP,
-            wall,
-            wall_transform,
-        );
-
-        let wall_transform = Transform::from(Vector3::new(SPRITE_WIDTH * -16.0, SPRITE_WIDTH * -6.0, 0.0));
-        let wall = world
-            .create_entity()
-            .build();
-
-        collision_world.add_collision(
-            world,
-            (HALF_WIDTH, HALF_WIDTH),
-            WALL_COLLISION_GROUP,
-            wall,
-            wall_transform,
-        );
-
-        // Initialise food
-        let objects_sheet_handle = load_sprite_sheet(
-            &mut world,
-            PngPath("textures/objects.png"),
-            RonPath("textures/objects.ron")
-        );
-
-        let blueberries_transform = Transform::from(Vector3::new(SPRITE_WIDTH * -4.0, SPRITE_WIDTH * -2.0, -1.0));
-        let blueberries = world
-            .create_entity()
-            .with(components::Food::new(components::FoodType::Blueberries))
-            .with(SpriteRender {
-                sprite_sheet: objects_sheet_handle.clone(),
-                sprite_number: 3
-            })
-            .build();
-
-        collision_world.add_collision(
-            &mut world,
-            (HALF_WIDTH, HALF_WIDTH),
-            FOOD_COLLISION_GROUP,
-            blueberries,
-            blueberries_transform
-        );
-
-        let apple_transform = Transform::from(Vector3::new(SPRITE_WIDTH * 0.0, SPRITE_WIDTH * 2.0, -1.0));
-        let apple = world
-            .create_entity()
-            .with(components::Food::new(components::FoodType::Apple))
-            .with(SpriteRender {
-                sprite_sheet: objects_sheet_handle.clone(),
-                sprite_number: 2
-            })
-            .build();
-
-        collision_world.add_collision(
-            &mut world,
-            (HALF_WIDTH, HALF_WIDTH),
-            FOOD_COLLISION_GROUP,
-            apple,
-            apple_transform
-        );
+        tile_map_collision::create_map_colliders(&mut world, &mut collision_world, &tile_map)?;
+
+        // Initialise the snake and the exit, food comes from the level data
+        let objects_sheet_handle = if self.headless {
+            None
+        } else {
+            Some(load_sprite_sheet(
+                &mut world,
+                PngPath("textures/objects.png"),
+                RonPath("textures/objects.ron")
+            )?)
+        };
 
         let snake_transform = Transform::from(Vector3::new(SPRITE_WIDTH * 3.0, SPRITE_WIDTH * 2.0, -1.0));
         let snake = world
             .create_entity()
-            .with(SpriteRender {
-                sprite_sheet: objects_sheet_handle.clone(),
-                sprite_number: 5
-            })
+            .with(components::Enemy { damage: 10 })
+            .with(components::HP { value: 4, max: 4 })
             .build();
+        attach_sprite(&mut world, snake, &objects_sheet_handle, 5)?;
 
         collision_world.add_collision(
             &mut world,
@@ -248,16 +244,13 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level1<'a, 'b> {
             ENEMY_COLLISION_GROUP,
             snake,
             snake_transform
-        );
+        )?;
 
         let exit_transform = Transform::from(Vector3::new(SPRITE_WIDTH * 12.0, SPRITE_WIDTH * -6.0, -1.0));
         let exit = world
             .create_entity()
-            .with(SpriteRender {
-                sprite_sheet: objects_sheet_handle.clone(),
-                sprite_number: 0
-            })
             .build();
+        attach_sprite(&mut world, exit, &objects_sheet_handle, 0)?;
 
         collision_world.add_collision(
             &mut world,
@@ -265,51 +258,220 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level1<'a, 'b> {
             EXIT_COLLISION_GROUP,
             exit,
             exit_transform
-        );
+        )?;
 
-        // initialise HUD elements
-        let font = world.read_resource::<Loader>().load(
-            "font/TestFont.ttf",
-            TtfFormat,
-            (),
-            &world.read_resource(),
-        );
+        if let Some(sheet) = objects_sheet_handle {
+            world.insert(systems::inventory::FoodSprites { sheet });
+        }
 
-        // probably should choose different anchor, but time
-        let hud_transform = UiTransform::new(
-            "hud text".to_string(),
-            Anchor::Middle,
-            Anchor::Middle,
-            -400.,
-            290.,
-            0.,
-            2000.,
-            500.,
-        );
+        // Initialise objects placed in the level data
+        check_map_objects(&tile_map)?;
+        let collected = self.save.as_ref().map_or(Vec::new(), |save| save.collected.clone());
+        spawn_map_objects(&mut world, &tile_map, &mut collision_world, &collected)?;
+        self.watcher = FileWatcher::new(self.watched_files(&tile_map));
 
-        let hud = world
-            .create_entity()
-            .with(hud_transform)
-            .with(UiText::new(
-                font.clone(),
-                "".to_string(),
-                [0.0, 0.0, 0.0, 1.0],
-                30.,
-            ))
-            .build();
+        // initialise HUD elements
+        if !self.headless {
+            initialise_hud(&mut world);
+        }
 
-        world.insert(systems::hud::Hud { entity: hud });
+        world.insert(systems::audio::Music { track: descriptor.music.clone() });
         world.insert(tile_map);
+        world.insert(descriptor);
         world.insert(Exiting {
             exiting: false
         });
         world.insert(collision_world);
+
+        // lives carry over between levels, so only set them up the first time
+        let lives = world.read_resource::<DeathConfig>().lives;
+        world.entry::<Lives>().or_insert_with(|| Lives { remaining: lives });
+
+        world.insert(CurrentLevel(self.level_path.clone()));
+        if let Some(save) = self.save.take() {
+            save.apply(world);
+        }
+
+        Ok(())
+    }
+
+    // headless levels only need the map data, not anything to draw it with
+    fn load_map(&self, world: &mut World) -> Result<(TileMap, Option<AmethystTileMap<TerrainTile>>), LevelLoadError> {
+        if self.headless {
+            Ok((TileMap::load_map(TmxFilePath(&self.map_path))?, None))
+        } else {
+            let (amethyst_map, tile_map) = create_map::<TerrainTile>(TmxFilePath(&self.map_path), world)?;
+            Ok((tile_map, Some(amethyst_map)))
+        }
+    }
+
+    fn watched_files(&self, tile_map: &TileMap) -> Vec<PathBuf> {
+        let mut files = vec![self.map_path.clone()];
+        if self.level_path != self.map_path {
+            files.push(self.level_path.clone());
+        }
+        if let Ok(tileset_image) = tile_map.tileset_image_path() {
+            files.push(tileset_image);
+        }
+        files
+    }
+
+    // swaps in a freshly loaded map underneath the player, who keeps their position and state
+    fn reload_map(&mut self, world: &mut World) -> Result<(), LevelLoadError> {
+        // load first, so a half-saved map leaves the old one in place
+        let (descriptor, map_path) = LevelDescriptor::load_for(&self.level_path)?;
+        self.map_path = map_path;
+        let (tile_map, amethyst_map) = self.load_map(world)?;
+        // and check the objects before tearing anything down, so a mistake in them can't leave half a map
+        check_map_objects(&tile_map)?;
+
+        let (reached, last_checkpoint) = save::checkpoint_progress(world);
+        let collected = save::collected_pickups(world);
+
+        let mut collision_world = world.remove::<CollisionWorld>().unwrap_or_default();
+        {
+            let entities = world.entities();
+            let map_part_storage = world.read_storage::<components::MapPart>();
+            let collider_storage = world.read_storage::<components::Collider>();
+            for (entity, _, collider) in (&entities, &map_part_storage, collider_storage.maybe()).join() {
+                if let Some(collider) = collider {
+                    collision_world.remove(collider);
+                }
+                entities.delete(entity).expect("Map part was already deleted");
+            }
+        }
+        world.maintain();
+
+        spawn_tile_map(world, amethyst_map);
+        let spawned = tile_map_collision::create_map_colliders(world, &mut collision_world, &tile_map)
+            .and_then(|_| spawn_map_objects(world, &tile_map, &mut collision_world, &collected));
+        world.insert(collision_world);
+        spawned?;
+        if !self.headless {
+            spawn_parallax(world, &descriptor, &tile_map)?;
+        }
+
+        // checkpoints are new entities now, so point the player at the one with the same id
+        save::restore_checkpoints(world, &reached, last_checkpoint);
+
+        self.watcher = FileWatcher::new(self.watched_files(&tile_map));
+        world.insert(systems::audio::Music { track: descriptor.music.clone() });
+        world.insert(tile_map);
+        world.insert(descriptor);
+        log::info!("Reloaded {}", self.map_path.display());
+        Ok(())
+    }
+
+    fn initialise_dispatcher(headless: bool) -> Dispatcher<'static, 'static> {
+        let mut dispatcher_builder = DispatcherBuilder::new();
+
+        // everything that reacts to input waits for it, so replays see the same order as the recording
+        dispatcher_builder.add(systems::input::InputSystem, "input_system", &[]);
+        dispatcher_builder.add(systems::motion::MotionSystem, "motion_system", &["input_system"]);
+        dispatcher_builder.add(systems::collision::CollisionSystem, "collision_system", &["motion_system"]);
+        if !headless {
+            dispatcher_builder.add(systems::hud::HudSystem::default(), "hud_system", &[]);
+            dispatcher_builder.add(systems::animation::AnimationSystem, "animation_system", &[]);
+            dispatcher_builder.add(systems::camera::CameraSystem, "camera_system", &["collision_system"]);
+            dispatcher_builder.add(systems::camera_effects::CameraEffectsSystem::default(), "camera_effects_system", &["camera_system"]);
+            dispatcher_builder.add(systems::parallax::ParallaxSystem, "parallax_system", &["camera_effects_system"]);
+            dispatcher_builder.add(systems::debug_draw::DebugDrawSystem, "debug_draw_system", &["collision_system"]);
+        }
+        dispatcher_builder.add(systems::death::DeathSystem, "death_system", &[]);
+        dispatcher_builder.add(systems::digestion::DigestionSystem, "digestion_system", &[]);
+        dispatcher_builder.add(systems::status_effects::StatusEffectSystem, "status_effect_system", &[]);
+        dispatcher_builder.add(systems::inventory::InventorySystem, "inventory_system", &["input_system"]);
+        dispatcher_builder.add(systems::attack::AttackSystem, "attack_system", &["input_system"]);
+        if !headless {
+            // after everything that sends popups, so they show up the frame they happen
+            dispatcher_builder.add(
+                systems::popups::PopupSystem::default(),
+                "popup_system",
+                &["camera_effects_system", "collision_system", "digestion_system", "status_effect_system", "inventory_system"]
+            );
+        }
+
+        dispatcher_builder.build()
+    }
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level1 {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        let StateData { world, .. } = data;
+
+        self.dispatcher.setup(world);
+        // a level that fails to load hands over to the error screen on its first update
+        if let Err(e) = self.load(world) {
+            self.load_error = Some(e);
+        }
+    }
+
+    fn handle_event(&mut self, data: StateData<GameData<'a, 'b>>, event: StateEvent) -> Trans<GameData<'a, 'b>, StateEvent> {
+        if let StateEvent::Window(event) = &event {
+            if is_key_down(&event, VirtualKeyCode::F2) {
+                return Trans::Push(Box::new(RebindMenu::default()));
+            }
+            // collider overlay
+            if is_key_down(&event, VirtualKeyCode::F3) {
+                let mut debug_draw = data.world.write_resource::<systems::debug_draw::DebugDraw>();
+                debug_draw.enabled = !debug_draw.enabled;
+            }
+        }
+        Trans::None
+    }
+
+    fn on_resume(&mut self, _data: StateData<GameData<'a, 'b>>) {
+        self.pause_held = true;
     }
 
     fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        if let Some(e) = self.load_error.take() {
+            return Trans::Switch(Box::new(LoadErrorScreen::new(&e)));
+        }
+        if data.world.remove::<QuitToTitle>().is_some() {
+            return Trans::Pop;
+        }
+
+        if !self.headless {
+            let pause_down = data.world
+                .read_resource::<InputHandler<GameBindings>>()
+                .action_is_down(&ActionBinding::Pause)
+                .unwrap_or(false);
+            let pressed = pause_down && !self.pause_held;
+            self.pause_held = pause_down;
+            if pressed {
+                return Trans::Push(Box::new(PauseMenu::default()));
+            }
+        }
+
+        let delta_seconds = data.world.read_resource::<Time>().delta_seconds();
+        if self.watcher.as_mut().map_or(false, |watcher| watcher.poll(delta_seconds)) {
+            if let Err(e) = self.reload_map(data.world) {
+                log::error!("Keeping the old map, the new one failed to load: {}", e);
+            }
+        }
+
         data.data.update(&data.world);
         self.dispatcher.dispatch(&data.world);
 
+        if data.world.read_resource::<Lives>().game_over() {
+            return Trans::Switch(Box::new(GameOver::default()));
+        }
+
+        // give the fade a moment to finish before leaving
+        let exiting = (&data.world.read_storage::<components::Player>())
+            .join()
+            .any(|player| match player.state {
+                components::PlayerState::Exiting => true,
+                _ => false
+            });
+        if exiting {
+            self.exit_timer += delta_seconds;
+            if self.exit_timer >= EXIT_FADE_SECONDS {
+                return Trans::Switch(Box::new(GameOver::won()));
+            }
+        }
+
         /*let exiting = data.world.write_resource::<Exiting>();
         if exiting.exiting {
             exiting.exiting = false;
@@ -319,15 +481,21 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level1<'a, 'b> {
         //}
     }
 
-    /*fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
-        for entity in  {
-            // if entity does not have player component, delete it
-            data.world.delete_entity(entity);
-        }
-    }*/
+    fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
+        data.world.read_resource::<InputMode>().save();
+        data.world.delete_all();
+    }
 }
 
-fn load_sprite_sheet(world: &mut World, png_path: PngPath, ron_path: RonPath) -> SpriteSheetHandle {
+fn load_sprite_sheet(world: &mut World, png_path: PngPath, ron_path: RonPath) -> Result<SpriteSheetHandle, LevelLoadError> {
+    // the loader only reports missing files once loading has moved on, so catch them here
+    let assets_dir = world.read_resource::<GamePaths>().assets.clone();
+    for path in &[png_path.0, ron_path.0] {
+        if !assets_dir.join(path).is_file() {
+            return Err(LevelLoadError::MissingAsset(assets_dir.join(path)));
+        }
+    }
+
     let texture_handle = {
         let loader = world.read_resource::<Loader>();
         let texture_storage = world.read_resource::<AssetStorage<Texture>>();
@@ -335,19 +503,265 @@ fn load_sprite_sheet(world: &mut World, png_path: PngPath, ron_path: RonPath) ->
     };
     let loader = world.read_resource::<Loader>();
     let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
-    loader.load(
+    Ok(loader.load(
         ron_path.0,
         SpriteSheetFormat(texture_handle),
         (),
         &sprite_sheet_store,
-    )
+    ))
+}
+
+fn spawn_tile_map(world: &mut World, amethyst_map: Option<AmethystTileMap<TerrainTile>>) {
+    if let Some(amethyst_map) = amethyst_map {
+        world
+            .create_entity()
+            .with(amethyst_map)
+            .with(Transform::default())
+            .with(components::MapPart)
+            .build();
+    }
+}
+
+// backgrounds from the level descriptor, then image and tile layers the map marks as parallax
+fn spawn_parallax(world: &mut World, descriptor: &LevelDescriptor, tile_map: &TileMap) -> Result<(), LevelLoadError> {
+    let assets_dir = world.read_resource::<GamePaths>().assets.clone();
+    let view = {
+        let screen_dimensions = world.read_resource::<ScreenDimensions>();
+        Vector2::new(screen_dimensions.width(), screen_dimensions.height())
+    };
+    let bounds = tile_map.world_bounds();
+
+    for layer in &descriptor.parallax {
+        spawn_parallax_image(world, layer, &assets_dir.join(&layer.image), view, bounds)?;
+    }
+    for layer in tile_map.parallax_image_layers() {
+        spawn_parallax_image(world, layer, Path::new(&layer.image), view, bounds)?;
+    }
+
+    let tile_layers = tile_map.parallax_tile_layers();
+    if tile_layers.is_empty() {
+        return Ok(());
+    }
+    let sheet = tile_map.load_tileset(world)?;
+    let map_size = bounds.1 - bounds.0;
+    for (layer, tiles) in tile_layers {
+        for offset in repeat_offsets(&layer, map_size, view, bounds) {
+            for (row, tile_row) in tiles.iter().enumerate() {
+                for (column, tile) in tile_row.iter().enumerate().filter(|(_, tile)| **tile != 0) {
+                    let origin = tile_map.tile_center(column as u32, row as u32) + offset;
+                    spawn_parallax_sprite(world, &layer, &sheet, *tile as usize - 1, origin);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn spawn_parallax_image(
+    world: &mut World,
+    layer: &ParallaxLayer,
+    image_path: &Path,
+    view: Vector2<f32>,
+    bounds: (Vector2<f32>, Vector2<f32>)
+) -> Result<(), LevelLoadError> {
+    let (width, height) = png_dimensions(image_path)
+        .map_err(|_| LevelLoadError::MissingAsset(image_path.to_path_buf()))?;
+
+    // the whole image is one sprite, so there's no sprite sheet file to go with it
+    let sheet = {
+        let loader = world.read_resource::<Loader>();
+        let texture = loader.load(
+            image_path.to_string_lossy().into_owned(),
+            ImageFormat::default(),
+            (),
+            &world.read_resource::<AssetStorage<Texture>>()
+        );
+        let sprite = Sprite::from_pixel_values(width, height, width, height, 0, 0, [0.0, 0.0], false, false);
+        loader.load_from_data(
+            SpriteSheet { texture, sprites: vec![sprite] },
+            (),
+            &world.read_resource::<AssetStorage<SpriteSheet>>()
+        )
+    };
+
+    for offset in repeat_offsets(layer, Vector2::new(width as f32, height as f32), view, bounds) {
+        spawn_parallax_sprite(world, layer, &sheet, 0, offset);
+    }
+    Ok(())
+}
+
+// where copies of a repeating layer need to go so it never runs out while the camera stays in the map
+fn repeat_offsets(
+    layer: &ParallaxLayer,
+    size: Vector2<f32>,
+    view: Vector2<f32>,
+    bounds: (Vector2<f32>, Vector2<f32>)
+) -> Vec<Vector2<f32>> {
+    // a layer scrolling at s is seen at camera * s relative to the camera
+    let axis = |repeat: bool, scroll: f32, size: f32, view: f32, min: f32, max: f32| -> Vec<f32> {
+        if !repeat || size <= 0.0 {
+            return vec![0.0];
+        }
+        let first = ((min * scroll - view / 2.0 - size) / size).floor() as i32;
+        let last = ((max * scroll + view / 2.0 + size) / size).ceil() as i32;
+        (first..=last).map(|copy| copy as f32 * size).collect()
+    };
+    let xs = axis(layer.repeat_x, layer.scroll.0, size.x, view.x, bounds.0.x, bounds.1.x);
+    let ys = axis(layer.repeat_y, layer.scroll.1, size.y, view.y, bounds.0.y, bounds.1.y);
+    xs.iter()
+        .flat_map(|x| ys.iter().map(move |y| Vector2::new(*x, *y)))
+        .collect()
+}
+
+fn spawn_parallax_sprite(
+    world: &mut World,
+    layer: &ParallaxLayer,
+    sheet: &SpriteSheetHandle,
+    sprite_number: usize,
+    origin: Vector2<f32>
+) {
+    world
+        .create_entity()
+        .with(Transform::from(Vector3::new(origin.x, origin.y, layer.z)))
+        .with(SpriteRender {
+            sprite_sheet: sheet.clone(),
+            sprite_number
+        })
+        .with(components::Parallax {
+            scroll: Vector2::new(layer.scroll.0, layer.scroll.1),
+            origin
+        })
+        .with(components::MapPart)
+        .build();
+}
+
+// everything spawn_map_objects could object to, checked before anything is spawned
+fn check_map_objects(tile_map: &TileMap) -> Result<(), LevelLoadError> {
+    for object in tile_map.objects() {
+        let reason = match object.obj_type.as_str() {
+            "checkpoint" if !has_area(object) => "checkpoints need a width and height",
+            "food" if TileMap::object_food_type(object).is_none() => "food needs a food property naming a food type",
+            _ => continue
+        };
+        return Err(LevelLoadError::BadObject {
+            id: object.id,
+            reason: reason.to_string()
+        });
+    }
+    Ok(())
+}
+
+fn has_area(object: &tiled::Object) -> bool {
+    let (width, height) = TileMap::object_size(object);
+    width > 0.0 && height > 0.0
+}
+
+// builds the colliders for objects placed in the level data, leaving out food that's been collected.
+// The objects have to have passed check_map_objects first
+fn spawn_map_objects(
+    world: &mut World,
+    tile_map: &TileMap,
+    collision_world: &mut CollisionWorld,
+    collected: &[u32]
+) -> Result<(), LevelLoadError> {
+    let food_sheet = world.try_fetch::<systems::inventory::FoodSprites>().map(|sprites| sprites.sheet.clone());
+    for object in tile_map.objects() {
+        let position = tile_map.object_position(object);
+        let object_transform = Transform::from(Vector3::new(position.x, position.y, -1.0));
+        match object.obj_type.as_str() {
+            "checkpoint" => {
+                let checkpoint = world
+                    .create_entity()
+                    .with(components::Checkpoint { id: object.id, reached: false })
+                    .with(components::MapPart)
+                    .build();
+
+                let (width, height) = TileMap::object_size(object);
+                collision_world.add_collision(
+                    world,
+                    (width / 2.0, height / 2.0),
+                    CHECKPOINT_COLLISION_GROUP,
+                    checkpoint,
+                    object_transform
+                )?;
+            },
+            "food" => {
+                if collected.contains(&object.id) {
+                    continue;
+                }
+                let food_type = match TileMap::object_food_type(object) {
+                    Some(food_type) => food_type,
+                    None => continue
+                };
+
+                let food = world
+                    .create_entity()
+                    .with(components::Food::new(food_type))
+                    .with(components::Pickup { id: object.id })
+                    .with(components::MapPart)
+                    .build();
+                attach_sprite(world, food, &food_sheet, food_type.sprite_number())?;
+
+                collision_world.add_collision(
+                    world,
+                    (HALF_WIDTH, HALF_WIDTH),
+                    FOOD_COLLISION_GROUP,
+                    food,
+                    object_transform
+                )?;
+            },
+            other => log::warn!("Unknown object type in level data: {:?}", other)
+        }
+    }
+    Ok(())
 }
 
 fn initialise_camera(world: &mut World, transform: Transform, camera: Camera) -> Entity {
+    let position = Vector2::new(transform.translation().x, transform.translation().y);
+
+    // covers the whole screen, camera effects fade it in and out
+    let fade_overlay = world
+        .create_entity()
+        .with(UiTransform::new(
+            "fade overlay".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            0.,
+            10.,
+            1.,
+            1.,
+        ).with_stretch(Stretch::XY { x_margin: 0., y_margin: 0., keep_aspect_ratio: false }))
+        .with(UiImage::SolidColor([0.0, 0.0, 0.0, 0.0]))
+        .build();
+
     world
         .create_entity()
         .with(transform)
         .with(camera)
+        .with(components::CameraFollow { focus: position, look_ahead: 0.0, position })
+        .with(systems::camera_effects::CameraEffects::new(Some(fade_overlay)))
         .build()
 }
 
+fn attach_sprite(
+    world: &mut World,
+    entity: Entity,
+    sheet: &Option<SpriteSheetHandle>,
+    sprite_number: usize
+) -> Result<(), LevelLoadError> {
+    if let Some(sheet) = sheet {
+        world
+            .write_storage()
+            .insert(entity, SpriteRender {
+                sprite_sheet: sheet.clone(),
+                sprite_number
+            })?;
+    }
+    Ok(())
+}
+
+fn initialise_hud(world: &mut World) {
+    // widgets are laid out in the prefab, HudSystem fills them in once it has loaded
+    world.exec(|mut creator: UiCreator<'_>| creator.create("ui/hud.ron", ()));
+}
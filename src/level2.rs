@@ -1,7 +1,7 @@
 use amethyst::{
     assets::{AssetStorage, Loader},
     core::{
-        math::{Vector2, Vector3, Point3},
+        math::{Vector2, Vector3},
         transform::Transform
     },
     ecs::{
@@ -9,17 +9,14 @@ use amethyst::{
     },
     prelude::{GameData, State, StateData, StateEvent, Trans},
     renderer::{
-        camera::{Camera, Projection},
+        camera::Camera,
         formats::texture::ImageFormat,
         sprite::{SpriteSheet, SpriteSheetHandle, SpriteRender},
         SpriteSheetFormat, Texture
     },
-    tiles::{Tile, TileMap as AmethystTileMap},
     window::ScreenDimensions,
-    ui::{Anchor, TtfFormat, UiText, UiTransform},
+    ui::UiCreator,
 };
-use ncollide2d as nc;
-use nalgebra as na;
 
 use crate::{
     util::{
@@ -29,10 +26,11 @@ use crate::{
     },
     components,
     tile::TerrainTile,
+    load_error::{LevelLoadError, LoadErrorScreen},
+    paths::GamePaths,
     tile_map::{TmxFilePath, create_map},
     systems,
     collision_world::*,
-    tile_map_collision,
 };
 
 
@@ -40,36 +38,27 @@ pub const SPRITE_WIDTH: f32 = 32.0;
 pub const HALF_WIDTH: f32 = SPRITE_WIDTH / 2.0;
 
 pub struct Level2<'a, 'b> {
-    dispatcher: Dispatcher<'a, 'b>
+    dispatcher: Dispatcher<'a, 'b>,
+    load_error: Option<LevelLoadError>
+}
+
+impl<'a, 'b> Default for Level2<'a, 'b> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a, 'b> Level2<'a, 'b> {
     pub fn new() -> Self {
         Level2 {
-            dispatcher: Level2::initialise_dispatcher()
+            dispatcher: Level2::initialise_dispatcher(),
+            load_error: None
         }
     }
 
-    fn initialise_dispatcher() -> Dispatcher<'a, 'b> {
-        let mut dispatcher_builder = DispatcherBuilder::new();
-
-        dispatcher_builder.add(systems::motion::MotionSystem, "motion_system", &[]);
-        dispatcher_builder.add(systems::collision::CollisionSystem, "collision_system", &[]);
-        dispatcher_builder.add(systems::input::InputSystem, "input_system", &[]);
-        dispatcher_builder.add(systems::hud::HudSystem, "hud_system", &[]);
-
-        dispatcher_builder.build()
-    }
-}
-
-impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
-    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
-        let StateData { mut world, .. } = data;
-
+    fn load(&mut self, mut world: &mut World) -> Result<(), LevelLoadError> {
         let mut collision_world = CollisionWorld::default();
 
-        self.dispatcher.setup(&mut world);
-
         let (width, height) = {
             let screen_dimensions = world.read_resource::<ScreenDimensions>();
             (screen_dimensions.width(), screen_dimensions.height())
@@ -79,11 +68,11 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
         initialise_camera(&mut world, Transform::from(Vector3::new(-HALF_WIDTH, HALF_WIDTH, 1.0)), Camera::standard_2d(width, height));
 
         // Initialise the tilemap
-        // panic with the given error message if we fail to load the tile map
+        let map_path = world.read_resource::<GamePaths>().resource("level1.tmx");
         let (amethyst_map, tile_map) = create_map::<TerrainTile>(
-            TmxFilePath(".\\resources\\level1.tmx"),
+            TmxFilePath(&map_path),
             &mut world
-        ).unwrap_or_else(|e| panic!("Problem loading tile map: {:?}", e));
+        )?;
 
 
         world
@@ -97,23 +86,21 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
             &mut world,
             PngPath("textures/player.png"),
             RonPath("textures/player.ron")
-        );
+        )?;
 
         let mut player_transform = Transform::default();
         player_transform.set_translation_xyz(SPRITE_WIDTH * -12.0, SPRITE_WIDTH * -6.0, 0.0);
 
         let player = world
             .create_entity()
-            .with(components::Player {
-                lr_input_state: 0.0,
-                snapback: Vector2::new(0.0, 0.0),
-                state: components::PlayerState::Idle,
-                belly: 0,
-            })
-            .with(components::HP { value: 30 })
+            .with(components::Player::new(
+                Vector2::new(player_transform.translation().x, player_transform.translation().y),
+                10
+            ))
+            .with(components::HP { value: 30, max: 30 })
             .with(components::Power { value: 1 })
             .with(SpriteRender {
-                sprite_sheet: player_sheet_handle.clone(),
+                sprite_sheet: player_sheet_handle,
                 sprite_number: 8
             })
             .with(components::Motion{
@@ -128,7 +115,7 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
             PLAYER_COLLISION_GROUP,
             player,
             player_transform
-        );
+        )?;
 
         // Initialise wall collision
         let wall_transform = Transform::from(Vector3::new(SPRITE_WIDTH * 15.0, SPRITE_WIDTH * -6.0, 0.0));
@@ -142,7 +129,7 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
             WALL_COLLISION_GROUP,
             wall,
             wall_transform,
-        );
+        )?;
 
         let wall_transform = Transform::from(Vector3::new(SPRITE_WIDTH * -16.0, SPRITE_WIDTH * -6.0, 0.0));
         let wall = world
@@ -155,14 +142,14 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
             WALL_COLLISION_GROUP,
             wall,
             wall_transform,
-        );
+        )?;
 
         // Initialise food
         let objects_sheet_handle = load_sprite_sheet(
             &mut world,
             PngPath("textures/objects.png"),
             RonPath("textures/objects.ron")
-        );
+        )?;
 
         let blueberries_transform = Transform::from(Vector3::new(SPRITE_WIDTH * -4.0, SPRITE_WIDTH * -2.0, -1.0));
         let blueberries = world
@@ -180,7 +167,7 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
             FOOD_COLLISION_GROUP,
             blueberries,
             blueberries_transform
-        );
+        )?;
 
         let apple_transform = Transform::from(Vector3::new(SPRITE_WIDTH * 0.0, SPRITE_WIDTH * 2.0, -1.0));
         let apple = world
@@ -198,7 +185,7 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
             FOOD_COLLISION_GROUP,
             apple,
             apple_transform
-        );
+        )?;
 
         let snake_transform = Transform::from(Vector3::new(SPRITE_WIDTH * 3.0, SPRITE_WIDTH * 2.0, -1.0));
         let snake = world
@@ -215,13 +202,13 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
             ENEMY_COLLISION_GROUP,
             snake,
             snake_transform
-        );
+        )?;
 
         let exit_transform = Transform::from(Vector3::new(SPRITE_WIDTH * 12.0, SPRITE_WIDTH * -6.0, -1.0));
         let exit = world
             .create_entity()
             .with(SpriteRender {
-                sprite_sheet: objects_sheet_handle.clone(),
+                sprite_sheet: objects_sheet_handle,
                 sprite_number: 0
             })
             .build();
@@ -232,52 +219,51 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
             EXIT_COLLISION_GROUP,
             exit,
             exit_transform
-        );
+        )?;
 
         // initialise HUD elements
-        let font = world.read_resource::<Loader>().load(
-            "font/TestFont.ttf",
-            TtfFormat,
-            (),
-            &world.read_resource(),
-        );
-
-        // probably should choose different anchor, but time
-        let hud_transform = UiTransform::new(
-            "hud text".to_string(),
-            Anchor::Middle,
-            Anchor::Middle,
-            -400.,
-            290.,
-            0.,
-            2000.,
-            500.,
-        );
-
-        let hud = world
-            .create_entity()
-            .with(hud_transform)
-            .with(UiText::new(
-                font.clone(),
-                "".to_string(),
-                [0.0, 0.0, 0.0, 1.0],
-                30.,
-            ))
-            .build();
+        world.exec(|mut creator: UiCreator<'_>| creator.create("ui/hud.ron", ()));
 
-        world.insert(systems::hud::Hud { entity: hud });
         world.insert(tile_map);
         world.insert(Exiting {
             exiting: false
         });
         world.insert(collision_world);
+
+        Ok(())
+    }
+
+    fn initialise_dispatcher() -> Dispatcher<'a, 'b> {
+        let mut dispatcher_builder = DispatcherBuilder::new();
+
+        dispatcher_builder.add(systems::motion::MotionSystem, "motion_system", &[]);
+        dispatcher_builder.add(systems::collision::CollisionSystem, "collision_system", &[]);
+        dispatcher_builder.add(systems::input::InputSystem, "input_system", &[]);
+        dispatcher_builder.add(systems::hud::HudSystem::default(), "hud_system", &[]);
+
+        dispatcher_builder.build()
+    }
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        let StateData { world, .. } = data;
+
+        self.dispatcher.setup(world);
+        if let Err(e) = self.load(world) {
+            self.load_error = Some(e);
+        }
     }
 
     fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        if let Some(e) = self.load_error.take() {
+            return Trans::Switch(Box::new(LoadErrorScreen::new(&e)));
+        }
+
         data.data.update(&data.world);
         self.dispatcher.dispatch(&data.world);
 
-        let exiting = data.world.write_resource::<Exiting>();
+        let _exiting = data.world.write_resource::<Exiting>();
         /*if exiting.exiting {
             exiting.exiting = false;
             Trans::Switch(Box::new(level2::Level2))
@@ -287,7 +273,14 @@ impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for Level2<'a, 'b> {
     }
 }
 
-fn load_sprite_sheet(world: &mut World, png_path: PngPath, ron_path: RonPath) -> SpriteSheetHandle {
+fn load_sprite_sheet(world: &mut World, png_path: PngPath, ron_path: RonPath) -> Result<SpriteSheetHandle, LevelLoadError> {
+    let assets_dir = world.read_resource::<GamePaths>().assets.clone();
+    for path in &[png_path.0, ron_path.0] {
+        if !assets_dir.join(path).is_file() {
+            return Err(LevelLoadError::MissingAsset(assets_dir.join(path)));
+        }
+    }
+
     let texture_handle = {
         let loader = world.read_resource::<Loader>();
         let texture_storage = world.read_resource::<AssetStorage<Texture>>();
@@ -295,12 +288,12 @@ fn load_sprite_sheet(world: &mut World, png_path: PngPath, ron_path: RonPath) ->
     };
     let loader = world.read_resource::<Loader>();
     let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
-    loader.load(
+    Ok(loader.load(
         ron_path.0,
         SpriteSheetFormat(texture_handle),
         (),
         &sprite_sheet_store,
-    )
+    ))
 }
 
 fn initialise_camera(world: &mut World, transform: Transform, camera: Camera) -> Entity {
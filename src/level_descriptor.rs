@@ -0,0 +1,61 @@
+//! Per-level settings that sit next to the TMX map: its name, the backgrounds drawn behind it and its music
+use std::path::{Path, PathBuf};
+use amethyst::config::Config;
+use serde::{Serialize, Deserialize};
+use crate::{
+    load_error::LevelLoadError,
+    paths
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParallaxLayer {
+    // under assets/, or an absolute path for images found in the map file
+    pub image: String,
+    // how much the layer moves with the camera, 0 stays put on screen and 1 moves like the level
+    pub scroll: (f32, f32),
+    pub repeat_x: bool,
+    pub repeat_y: bool,
+    // more negative is further back, the level itself sits at 0
+    pub z: f32,
+}
+
+impl Default for ParallaxLayer {
+    fn default() -> Self {
+        ParallaxLayer {
+            image: String::new(),
+            scroll: (0.5, 0.5),
+            repeat_x: true,
+            repeat_y: false,
+            z: -5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelDescriptor {
+    pub name: String,
+    // relative to the descriptor
+    pub map: String,
+    pub parallax: Vec<ParallaxLayer>,
+    // under assets/, looped while the level is running
+    pub music: Option<String>,
+}
+
+impl LevelDescriptor {
+    /// Loads a `.ron` descriptor, or wraps a bare `.tmx` map in the defaults.
+    /// Returns the descriptor along with the resolved path of its map.
+    pub fn load_for(path: &Path) -> Result<(Self, PathBuf), LevelLoadError> {
+        if path.extension().map_or(false, |extension| extension == "tmx") {
+            let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            return Ok((LevelDescriptor { name, ..Default::default() }, path.to_path_buf()));
+        }
+
+        let descriptor = LevelDescriptor::load_no_fallback(path)
+            .map_err(|e| LevelLoadError::Descriptor { path: path.to_path_buf(), error: e.to_string() })?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let map_path = paths::resolve(directory, &descriptor.map);
+        Ok((descriptor, map_path))
+    }
+}
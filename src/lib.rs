@@ -0,0 +1,30 @@
+// every system's SystemData is a tuple of storages and resources, which is how amethyst wants them
+#![allow(clippy::type_complexity)]
+
+pub mod components;
+pub mod tile_map;
+pub mod level1;
+pub mod level2;
+pub mod systems;
+pub mod collision_world;
+pub mod util;
+pub mod tile;
+pub mod tile_map_collision;
+pub mod config;
+pub mod game_over;
+pub mod food;
+pub mod bindings;
+pub mod paths;
+pub mod rebind;
+pub mod gamepad;
+pub mod recording;
+pub mod input_buffer;
+pub mod headless;
+pub mod cli;
+pub mod load_error;
+pub mod validate;
+pub mod hot_reload;
+pub mod level_descriptor;
+pub mod menu;
+pub mod settings;
+pub mod save;
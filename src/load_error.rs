@@ -0,0 +1,142 @@
+//! Everything that can go wrong while building a level, and the screen shown when it does
+use std::{
+    error::Error,
+    fmt,
+    io,
+    path::PathBuf
+};
+use amethyst::{
+    assets::Loader,
+    ecs::{
+        error::Error as EcsError,
+        prelude::{Builder, Entity, WorldExt}
+    },
+    input::is_key_down,
+    prelude::{GameData, State, StateData, StateEvent, Trans},
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+    winit::VirtualKeyCode
+};
+
+#[derive(Debug)]
+pub enum LevelLoadError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    TmxParse {
+        path: PathBuf,
+        error: tiled::TiledError,
+    },
+    MissingTileset,
+    MissingTilesetImage,
+    MissingTileLayer,
+    BadObject {
+        id: u32,
+        reason: String,
+    },
+    MissingAsset(PathBuf),
+    Descriptor {
+        path: PathBuf,
+        error: String,
+    },
+    Entity(EcsError),
+}
+
+impl fmt::Display for LevelLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelLoadError::Io { path, error } => write!(f, "Could not read {}: {}", path.display(), error),
+            LevelLoadError::TmxParse { path, error } => write!(f, "Could not parse {}: {:?}", path.display(), error),
+            LevelLoadError::MissingTileset => write!(f, "No tilesets were found."),
+            LevelLoadError::MissingTilesetImage => write!(f, "No images for the tileset were found."),
+            LevelLoadError::MissingTileLayer => write!(f, "The map has no tile layer."),
+            LevelLoadError::BadObject { id, reason } => write!(f, "Object {} in the level data is invalid: {}", id, reason),
+            LevelLoadError::MissingAsset(path) => write!(f, "Missing asset {}", path.display()),
+            LevelLoadError::Descriptor { path, error } => write!(f, "Could not load level descriptor {}: {}", path.display(), error),
+            LevelLoadError::Entity(error) => write!(f, "Could not set up a level entity: {}", error),
+        }
+    }
+}
+
+impl Error for LevelLoadError {}
+
+impl From<EcsError> for LevelLoadError {
+    fn from(error: EcsError) -> Self {
+        LevelLoadError::Entity(error)
+    }
+}
+
+/// Shown instead of a level that failed to load. Going back pops to whatever state started the level.
+pub struct LoadErrorScreen {
+    message: String,
+    text: Option<Entity>,
+}
+
+impl LoadErrorScreen {
+    pub fn new(error: &LevelLoadError) -> Self {
+        LoadErrorScreen {
+            message: error.to_string(),
+            text: None,
+        }
+    }
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for LoadErrorScreen {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        let StateData { world, .. } = data;
+
+        log::error!("Level failed to load: {}", self.message);
+
+        let font = world.read_resource::<Loader>().load(
+            "font/TestFont.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        let transform = UiTransform::new(
+            "load error text".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            0.,
+            0.,
+            1000.,
+            300.,
+        );
+
+        self.text = Some(world
+            .create_entity()
+            .with(transform)
+            .with(UiText::new(
+                font,
+                format!("Couldn't load the level:\n{}\n\nPress Enter to go back", self.message),
+                [0.0, 0.0, 0.0, 1.0],
+                25.,
+            ))
+            .build());
+    }
+
+    fn handle_event(&mut self, _data: StateData<GameData<'a, 'b>>, event: StateEvent) -> Trans<GameData<'a, 'b>, StateEvent> {
+        if let StateEvent::Window(event) = &event {
+            if is_key_down(&event, VirtualKeyCode::Return) || is_key_down(&event, VirtualKeyCode::Escape) {
+                return Trans::Pop;
+            }
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        data.data.update(&data.world);
+        Trans::None
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
+        // the states underneath still own everything else
+        if let Some(text) = self.text.take() {
+            if let Err(e) = data.world.delete_entity(text) {
+                log::error!("Failed to clean up load error screen: {}", e);
+            }
+        }
+    }
+}
@@ -1,47 +1,114 @@
-use std::time::Duration;
+use std::{
+    env,
+    path::Path,
+    process,
+    time::{SystemTime, UNIX_EPOCH}
+};
 use amethyst::{
     animation::AnimationBundle,
     assets::PrefabLoaderSystemDesc,
+    audio::AudioBundle,
     config::Config,
-    core::{
-        SystemBundle,
-        frame_limiter::FrameRateLimitStrategy,
-        transform::TransformBundle
-    },
-    input::{InputBundle, StringBindings},
+    core::transform::{Transform, TransformBundle},
+    ecs::prelude::{Join, WorldExt},
+    input::InputBundle,
     prelude::{Application, GameDataBuilder},
     renderer::{
         types::DefaultBackend,
         sprite::SpriteRender,
         RenderDebugLines, RenderFlat2D, RenderToWindow, RenderingBundle
     },
-    tiles::{RenderTiles2D, Tile, TileMap},
-    ui::{RenderUi, DrawUi, UiBundle},
-    utils::application_root_dir
+    tiles::RenderTiles2D,
+    ui::{RenderUi, UiBundle},
+    window::DisplayConfig,
+    LoggerConfig
 };
 
-mod components;
-mod tile_map;
-mod level1;
-mod level2;
-mod systems;
-mod collision_world;
-mod util;
-mod tile;
-mod tile_map_collision;
+use ld45::{
+    bindings,
+    cli,
+    components,
+    config,
+    food,
+    gamepad,
+    headless,
+    input_buffer,
+    level1,
+    menu,
+    paths,
+    recording,
+    settings,
+    systems::audio,
+    tile,
+    util,
+    validate
+};
 
-const FRAME_LIMIT: u32 = 60;
+// how long a headless run lasts when there's no replay to set the length
+const HEADLESS_TICKS: usize = 600;
 
 fn main() -> amethyst::Result<()> {
-    amethyst::start_logger(Default::default());
+    let args = match cli::Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    amethyst::start_logger(LoggerConfig {
+        level_filter: args.log_level,
+        ..Default::default()
+    });
+
+    let mut game_paths = paths::GamePaths::from_application_root()?;
+    if let Some(config_dir) = &args.config_dir {
+        game_paths = game_paths.with_resources_dir(config_dir);
+    }
+    let level_path = match &args.level {
+        Some(level) => paths::absolute(level),
+        None => game_paths.resource("level1.ron"),
+    };
+
+    if args.validate_assets {
+        return validate_assets(&game_paths, &level_path);
+    }
+    if args.headless {
+        return run_headless(&args, &game_paths, &level_path);
+    }
+
+    // command line arguments win over the player's settings, which win over display.ron
+    let settings = settings::Settings::load_user();
+    let mut display_config = DisplayConfig::load(game_paths.resource("display.ron"));
+    settings.apply_to_display(&mut display_config);
+    if let Some(dimensions) = args.window_size {
+        display_config.dimensions = Some(dimensions);
+    }
+
+    let key_bindings_path = game_paths.resource("input.ron");
+    let mut key_bindings = bindings::load_bindings(&key_bindings_path, &paths::user_bindings_path())?;
 
-    let app_root = application_root_dir()?;
+    let controller_config = gamepad::ControllerConfig::load(game_paths.resource("controllers.ron"));
+    controller_config.apply(&mut key_bindings);
 
-    let display_config_path = app_root.join("resources/display.ron");
+    let death_config = config::DeathConfig::load(game_paths.resource("death.ron"));
+    let digestion_config = config::DigestionConfig::load(game_paths.resource("digestion.ron"));
+    let food_table = food::FoodTable::load(game_paths.resource("food.ron"));
+    let input_buffer_config = input_buffer::InputBufferConfig::load(game_paths.resource("input_buffer.ron"));
+    let camera_config = config::CameraConfig::load(game_paths.resource("camera.ron"));
 
-    let assets_dir = app_root.join("assets/");
+    let clock_seed = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
 
-    let key_bindings_path = app_root.join("resources/input.ron");
+    let input_mode = match (&args.record, &args.replay) {
+        (_, Some(replay_path)) => recording::InputMode::replay(recording::Recording::from_file(replay_path)?),
+        (Some(record_path), None) => recording::InputMode::record(record_path.clone(), clock_seed),
+        (None, None) => recording::InputMode::Live,
+    };
+    let seed = input_mode.seed().unwrap_or(clock_seed);
 
     let game_data = GameDataBuilder::default()
         .with_system_desc(
@@ -50,8 +117,8 @@ fn main() -> amethyst::Result<()> {
             &[],
         )
         .with_bundle(
-            InputBundle::<StringBindings>::new()
-                .with_bindings_from_file(&key_bindings_path)?
+            InputBundle::<bindings::GameBindings>::new()
+                .with_bindings(key_bindings)
         )?
         .with_bundle(AnimationBundle::<level1::AnimationId, SpriteRender>::new(
             "sprite_animation_control",
@@ -62,7 +129,7 @@ fn main() -> amethyst::Result<()> {
         .with_bundle(
             RenderingBundle::<DefaultBackend>::new()
                 .with_plugin(
-                    RenderToWindow::from_config_path(display_config_path)
+                    RenderToWindow::from_config(display_config)
                         .with_clear([0.6, 0.85, 0.91, 1.0]),
                 )
                 .with_plugin(RenderFlat2D::default())
@@ -70,15 +137,70 @@ fn main() -> amethyst::Result<()> {
                 .with_plugin(RenderTiles2D::<tile::TerrainTile>::default())
                 .with_plugin(RenderDebugLines::default()),
         )?
-        .with_bundle(UiBundle::<StringBindings>::new())?;
+        .with_bundle(UiBundle::<bindings::GameBindings>::new())?
+        .with_bundle(AudioBundle::default())?
+        // SoundSystem sets up the output itself, so a missing device just means silence
+        .with(audio::SoundSystem::default(), "sound_system", &[])
+        .with(audio::MusicSystem::default(), "music_system", &[]);
 
-    let mut game = Application::build(assets_dir, level1::Level1::new())?
-        .with_frame_limit(
-            FrameRateLimitStrategy::SleepAndYield(Duration::from_millis(2)),
-            FRAME_LIMIT,
-        )
+    let (frame_strategy, frame_limit) = settings::frame_rate(args.frame_limit.or(settings.frame_limit));
+    let mut game = Application::build(&game_paths.assets, menu::MainMenu::new(level_path))?
+        .with_resource(death_config)
+        .with_resource(digestion_config)
+        .with_resource(food_table)
+        .with_resource(controller_config)
+        .with_resource(input_mode)
+        .with_resource(input_buffer_config)
+        .with_resource(camera_config)
+        .with_resource(util::Rng::new(seed))
+        .with_resource(game_paths.clone())
+        .with_resource(settings)
+        .with_frame_limit(frame_strategy, frame_limit)
         .build(game_data)?;
 
     game.run();
     Ok(())
-}
\ No newline at end of file
+}
+
+fn run_headless(args: &cli::Args, game_paths: &paths::GamePaths, level_path: &Path) -> amethyst::Result<()> {
+    let script = match &args.replay {
+        Some(replay_path) => recording::Recording::from_file(replay_path)?.frames,
+        None => Vec::new(),
+    };
+    let ticks = args.ticks.unwrap_or(if script.is_empty() { HEADLESS_TICKS } else { script.len() });
+
+    let mut simulation = headless::Simulation::new(game_paths, level_path, script)?;
+    simulation.run(ticks);
+
+    let world = simulation.world();
+    let player_storage = world.read_storage::<components::Player>();
+    let hp_storage = world.read_storage::<components::HP>();
+    let transform_storage = world.read_storage::<Transform>();
+    for (player, hp, transform) in (&player_storage, &hp_storage, &transform_storage).join() {
+        log::info!(
+            "After {} ticks: player at ({}, {}), HP {}/{}, belly {}/{}",
+            ticks,
+            transform.translation().x,
+            transform.translation().y,
+            hp.value,
+            hp.max,
+            player.belly,
+            player.belly_max()
+        );
+    }
+    Ok(())
+}
+
+fn validate_assets(game_paths: &paths::GamePaths, level_path: &Path) -> amethyst::Result<()> {
+    let problems = validate::validate_assets(game_paths, &[level_path.to_path_buf()]);
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+
+    if problems.is_empty() {
+        log::info!("All assets are valid");
+        Ok(())
+    } else {
+        Err(amethyst::Error::from_string(format!("Found {} problem(s) with the assets", problems.len())))
+    }
+}
@@ -0,0 +1,621 @@
+//! Keyboard and controller driven menus: the title screen, the pause, options and save slot menus, and the list widget they share
+use std::path::PathBuf;
+use amethyst::{
+    assets::Loader,
+    ecs::prelude::{Builder, Entity, World, WorldExt},
+    input::InputHandler,
+    prelude::{GameData, State, StateData, StateEvent, Trans},
+    ui::{Anchor, Stretch, TtfFormat, UiImage, UiText, UiTransform},
+    window::ScreenDimensions,
+    winit::VirtualKeyCode
+};
+use crate::{
+    bindings::{ActionBinding, AxisBinding, GameBindings},
+    gamepad::ControllerConfig,
+    level1::Level1,
+    paths::GamePaths,
+    rebind::RebindMenu,
+    save::{SaveGame, SAVE_SLOTS},
+    settings::Settings,
+    systems::{
+        audio::Music,
+        death::Lives
+    }
+};
+
+const LINE_HEIGHT: f32 = 50.;
+const NORMAL_COLOUR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const SELECTED_COLOUR: [f32; 4] = [0.8, 0.1, 0.1, 1.0];
+const DISABLED_COLOUR: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+// how far a stick has to be pushed to move the selection
+const STICK_THRESHOLD: f32 = 0.5;
+
+pub enum MenuChoice {
+    Picked(usize),
+    // left or right on an item, -1 or 1
+    Adjust(usize, i32),
+    Back,
+}
+
+pub struct MenuItem {
+    pub label: String,
+    pub enabled: bool,
+}
+
+impl MenuItem {
+    pub fn new<S: Into<String>>(label: S) -> Self {
+        MenuItem { label: label.into(), enabled: true }
+    }
+
+    pub fn disabled<S: Into<String>>(label: S) -> Self {
+        MenuItem { label: label.into(), enabled: false }
+    }
+}
+
+// what the menu cares about this frame, from the keyboard or any controller
+#[derive(Default, Clone, Copy)]
+struct MenuButtons {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+    confirm: bool,
+    back: bool,
+}
+
+impl MenuButtons {
+    fn read(world: &World) -> Self {
+        let input = world.read_resource::<InputHandler<GameBindings>>();
+        let controller_config = world.read_resource::<ControllerConfig>();
+
+        let axis = |keyboard: AxisBinding, pad: fn(u32) -> AxisBinding| {
+            input.axis_value(&keyboard).unwrap_or(0.0)
+                + controller_config.profiles
+                    .iter()
+                    .filter_map(|profile| input
+                        .axis_value(&pad(profile.controller_id))
+                        .map(|value| profile.process(value)))
+                    .sum::<f32>()
+        };
+        let vertical = axis(AxisBinding::Vertical, AxisBinding::PadVertical);
+        let horizontal = axis(AxisBinding::Horizontal, AxisBinding::PadHorizontal);
+        let action = |action: ActionBinding| input.action_is_down(&action).unwrap_or(false);
+
+        // Enter and Escape always work, so rebinding can't lock anyone out of the menus
+        MenuButtons {
+            up: vertical > STICK_THRESHOLD,
+            down: vertical < -STICK_THRESHOLD,
+            left: horizontal < -STICK_THRESHOLD,
+            right: horizontal > STICK_THRESHOLD,
+            confirm: action(ActionBinding::Interact) || action(ActionBinding::Jump) || input.key_is_down(VirtualKeyCode::Return),
+            back: action(ActionBinding::Pause) || input.key_is_down(VirtualKeyCode::Escape),
+        }
+    }
+}
+
+/// A vertical list of options. Call `update` every frame, and `delete` when the screen goes away.
+pub struct Menu {
+    entities: Vec<Entity>,
+    lines: Vec<Entity>,
+    items: Vec<MenuItem>,
+    selected: usize,
+    held: MenuButtons,
+}
+
+impl Menu {
+    pub fn new(world: &mut World, title: &str, items: Vec<MenuItem>) -> Self {
+        let font = world.read_resource::<Loader>().load(
+            "font/TestFont.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        let top = (items.len() as f32 * LINE_HEIGHT) / 2.0;
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "menu title".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                top + LINE_HEIGHT * 1.5,
+                2.,
+                1000.,
+                80.,
+            ))
+            .with(UiText::new(font.clone(), title.to_string(), NORMAL_COLOUR, 50.))
+            .build();
+
+        let lines = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("menu line {}", index),
+                    Anchor::Middle,
+                    Anchor::Middle,
+                    0.,
+                    top - index as f32 * LINE_HEIGHT,
+                    2.,
+                    600.,
+                    LINE_HEIGHT,
+                ))
+                .with(UiText::new(font.clone(), item.label.clone(), NORMAL_COLOUR, 30.))
+                .build())
+            .collect::<Vec<Entity>>();
+
+        let menu = Menu {
+            entities: vec![title],
+            lines,
+            selected: items.iter().position(|item| item.enabled).unwrap_or(0),
+            items,
+            // whatever was held to open the menu has to be let go before it counts
+            held: MenuButtons::read(world),
+        };
+        menu.refresh(world);
+        menu
+    }
+
+    // a translucent sheet behind the menu, for menus shown over the level
+    pub fn with_backdrop(mut self, world: &mut World) -> Self {
+        let backdrop = world
+            .create_entity()
+            .with(UiTransform::new(
+                "menu backdrop".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                0.,
+                1.,
+                1.,
+                1.,
+            ).with_stretch(Stretch::XY { x_margin: 0., y_margin: 0., keep_aspect_ratio: false }))
+            .with(UiImage::SolidColor([1.0, 1.0, 1.0, 0.6]))
+            .build();
+        self.entities.push(backdrop);
+        self
+    }
+
+    pub fn update(&mut self, world: &mut World) -> Option<MenuChoice> {
+        let buttons = MenuButtons::read(world);
+        let pressed = MenuButtons {
+            up: buttons.up && !self.held.up,
+            down: buttons.down && !self.held.down,
+            left: buttons.left && !self.held.left,
+            right: buttons.right && !self.held.right,
+            confirm: buttons.confirm && !self.held.confirm,
+            back: buttons.back && !self.held.back,
+        };
+        self.held = buttons;
+
+        if pressed.up {
+            self.step(self.items.len() - 1);
+        }
+        if pressed.down {
+            self.step(1);
+        }
+        if pressed.up || pressed.down {
+            self.refresh(world);
+        }
+
+        let enabled = self.items.get(self.selected).map_or(false, |item| item.enabled);
+        if pressed.back {
+            Some(MenuChoice::Back)
+        } else if pressed.confirm && enabled {
+            Some(MenuChoice::Picked(self.selected))
+        } else if (pressed.left || pressed.right) && enabled {
+            Some(MenuChoice::Adjust(self.selected, if pressed.left { -1 } else { 1 }))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_label(&mut self, world: &mut World, index: usize, label: String) {
+        if let Some(line) = self.lines.get(index) {
+            if let Some(text) = world.write_storage::<UiText>().get_mut(*line) {
+                text.text = label.clone();
+            }
+        }
+        if let Some(item) = self.items.get_mut(index) {
+            item.label = label;
+        }
+    }
+
+    pub fn delete(&mut self, world: &mut World) {
+        let entities = self.entities.drain(..).chain(self.lines.drain(..)).collect::<Vec<Entity>>();
+        if let Err(e) = world.delete_entities(&entities) {
+            log::error!("Failed to clean up menu: {}", e);
+        }
+    }
+
+    // moves the selection by `offset` places, wrapping around and skipping anything disabled
+    fn step(&mut self, offset: usize) {
+        let count = self.items.len();
+        for _ in 0..count {
+            self.selected = (self.selected + offset) % count;
+            if self.items[self.selected].enabled {
+                break;
+            }
+        }
+    }
+
+    fn refresh(&self, world: &mut World) {
+        let mut text_storage = world.write_storage::<UiText>();
+        for (index, (line, item)) in self.lines.iter().zip(&self.items).enumerate() {
+            if let Some(text) = text_storage.get_mut(*line) {
+                text.color = if !item.enabled {
+                    DISABLED_COLOUR
+                } else if index == self.selected {
+                    SELECTED_COLOUR
+                } else {
+                    NORMAL_COLOUR
+                };
+            }
+        }
+    }
+}
+
+/// Where the game starts. Levels are pushed on top of it, so leaving one comes back here.
+pub struct MainMenu {
+    level_path: PathBuf,
+    menu: Option<Menu>,
+}
+
+impl MainMenu {
+    pub fn new(level_path: PathBuf) -> Self {
+        MainMenu { level_path, menu: None }
+    }
+
+    fn show(&mut self, world: &mut World) {
+        // the title is quiet, so whatever the last level was playing fades out
+        world.insert(Music::default());
+        let any_saves = (0..SAVE_SLOTS).any(|slot| SaveGame::read_slot(slot).is_some());
+        let item = |label: &str, enabled: bool| if enabled { MenuItem::new(label) } else { MenuItem::disabled(label) };
+        self.menu = Some(Menu::new(world, "Guinea Pig Adventure", vec![
+            MenuItem::new("Start"),
+            item("Continue", SaveGame::newest_slot().is_some()),
+            item("Load game", any_saves),
+            MenuItem::new("Options"),
+            MenuItem::new("Quit"),
+        ]));
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.delete(world);
+        }
+    }
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for MainMenu {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        // fullscreen needs a window to go fullscreen on, so it's applied here rather than in main
+        data.world.read_resource::<Settings>().apply_fullscreen(data.world);
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.hide(data.world);
+    }
+
+    // levels clear out the world when they stop, so the menu is rebuilt rather than kept around
+    fn on_pause(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.show(data.world);
+    }
+
+    fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        data.data.update(&data.world);
+
+        let choice = self.menu.as_mut().and_then(|menu| menu.update(data.world));
+        match choice {
+            Some(MenuChoice::Picked(0)) => {
+                // lives carry over between levels, but not from a run that was quit to the title
+                data.world.remove::<Lives>();
+                Trans::Push(Box::new(Level1::new(self.level_path.clone())))
+            },
+            // picks up from the most recent save
+            Some(MenuChoice::Picked(1)) => match SaveGame::newest_slot().and_then(SaveGame::read_slot) {
+                Some(Ok(save)) => {
+                    let level_path = save.level_path(&data.world.read_resource::<GamePaths>());
+                    Trans::Push(Box::new(Level1::from_save(level_path, save)))
+                },
+                _ => Trans::None,
+            },
+            Some(MenuChoice::Picked(2)) => Trans::Push(Box::new(SlotMenu::load())),
+            Some(MenuChoice::Picked(3)) => Trans::Push(Box::new(OptionsMenu::default())),
+            Some(MenuChoice::Picked(_)) | Some(MenuChoice::Back) => Trans::Quit,
+            Some(MenuChoice::Adjust(..)) | None => Trans::None,
+        }
+    }
+}
+
+/// Left in the world by the pause menu so the level underneath pops itself on its next update
+pub struct QuitToTitle;
+
+/// Pushed over a level, which stops its dispatcher from running until this is popped
+#[derive(Default)]
+pub struct PauseMenu {
+    menu: Option<Menu>,
+}
+
+impl PauseMenu {
+    fn show(&mut self, world: &mut World) {
+        self.menu = Some(Menu::new(world, "Paused", vec![
+            MenuItem::new("Resume"),
+            MenuItem::new("Save game"),
+            MenuItem::new("Options"),
+            MenuItem::new("Quit to title"),
+        ]).with_backdrop(world));
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.delete(world);
+        }
+    }
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for PauseMenu {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.hide(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.show(data.world);
+    }
+
+    fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        // only the game-wide systems run, so rendering and the UI carry on while the level stands still
+        data.data.update(&data.world);
+
+        let choice = self.menu.as_mut().and_then(|menu| menu.update(data.world));
+        match choice {
+            Some(MenuChoice::Picked(0)) | Some(MenuChoice::Back) => Trans::Pop,
+            Some(MenuChoice::Picked(1)) => Trans::Push(Box::new(SlotMenu::save())),
+            Some(MenuChoice::Picked(2)) => Trans::Push(Box::new(OptionsMenu::default())),
+            // pops this menu, and the level underneath follows it
+            Some(MenuChoice::Picked(_)) => {
+                data.world.insert(QuitToTitle);
+                Trans::Pop
+            },
+            Some(MenuChoice::Adjust(..)) | None => Trans::None,
+        }
+    }
+}
+
+/// Lists the save slots, either to save the level paused underneath or to load one from the title screen
+pub struct SlotMenu {
+    saving: bool,
+    menu: Option<Menu>,
+}
+
+impl SlotMenu {
+    pub fn save() -> Self {
+        SlotMenu { saving: true, menu: None }
+    }
+
+    pub fn load() -> Self {
+        SlotMenu { saving: false, menu: None }
+    }
+
+    fn label(slot: usize, contents: &str) -> String {
+        format!("Slot {}: {}", slot + 1, contents)
+    }
+
+    fn show(&mut self, world: &mut World) {
+        let mut items = (0..SAVE_SLOTS)
+            .map(|slot| match SaveGame::read_slot(slot) {
+                Some(Ok(save)) => MenuItem::new(SlotMenu::label(slot, &save.describe())),
+                // anything can be saved over, but only real saves can be loaded
+                Some(Err(e)) => {
+                    log::error!("{}", e);
+                    let label = SlotMenu::label(slot, "Unreadable");
+                    if self.saving { MenuItem::new(label) } else { MenuItem::disabled(label) }
+                },
+                None => {
+                    let label = SlotMenu::label(slot, "Empty");
+                    if self.saving { MenuItem::new(label) } else { MenuItem::disabled(label) }
+                }
+            })
+            .collect::<Vec<MenuItem>>();
+        items.push(MenuItem::new("Back"));
+
+        let title = if self.saving { "Save game" } else { "Load game" };
+        let menu = Menu::new(world, title, items);
+        self.menu = Some(if self.saving { menu.with_backdrop(world) } else { menu });
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.delete(world);
+        }
+    }
+
+    fn save_to(&mut self, world: &mut World, slot: usize) {
+        let label = match SaveGame::capture(world).and_then(|save| save.write_slot(slot).map(|_| save)) {
+            Ok(save) => SlotMenu::label(slot, &save.describe()),
+            Err(e) => {
+                log::error!("Could not save the game: {}", e);
+                SlotMenu::label(slot, "Could not save")
+            }
+        };
+        if let Some(menu) = &mut self.menu {
+            menu.set_label(world, slot, label);
+        }
+    }
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for SlotMenu {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.hide(data.world);
+    }
+
+    fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        data.data.update(&data.world);
+
+        let choice = self.menu.as_mut().and_then(|menu| menu.update(data.world));
+        match choice {
+            Some(MenuChoice::Picked(slot)) if slot < SAVE_SLOTS && self.saving => {
+                self.save_to(data.world, slot);
+                Trans::None
+            },
+            // takes this menu's place, so leaving the level goes back to the title
+            Some(MenuChoice::Picked(slot)) if slot < SAVE_SLOTS => match SaveGame::read_slot(slot) {
+                Some(Ok(save)) => {
+                    let level_path = save.level_path(&data.world.read_resource::<GamePaths>());
+                    Trans::Switch(Box::new(Level1::from_save(level_path, save)))
+                },
+                _ => Trans::None,
+            },
+            Some(MenuChoice::Picked(_)) | Some(MenuChoice::Back) => Trans::Pop,
+            Some(MenuChoice::Adjust(..)) | None => Trans::None,
+        }
+    }
+}
+
+// window sizes on offer, all the same shape as the default 1024x512
+const RESOLUTIONS: [(u32, u32); 5] = [(800, 400), (1024, 512), (1280, 640), (1600, 800), (1920, 960)];
+const FRAME_LIMITS: [Option<u32>; 5] = [Some(30), Some(60), Some(120), Some(144), None];
+const VOLUME_STEP: f32 = 0.1;
+
+const RESOLUTION: usize = 0;
+const FULLSCREEN: usize = 1;
+const FRAME_LIMIT: usize = 2;
+const MUSIC_VOLUME: usize = 3;
+const SOUND_VOLUME: usize = 4;
+const KEY_BINDINGS: usize = 5;
+
+/// Changes the `Settings` resource as things are picked, saving them to the user settings file each time
+#[derive(Default)]
+pub struct OptionsMenu {
+    menu: Option<Menu>,
+}
+
+impl OptionsMenu {
+    fn labels(world: &World) -> Vec<String> {
+        let settings = world.read_resource::<Settings>();
+        let (width, height) = OptionsMenu::window_size(world, &settings);
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+        let percent = |volume: f32| format!("{}%", (volume * 100.0).round());
+        vec![
+            format!("Resolution: {}x{}", width, height),
+            format!("Fullscreen: {}", on_off(settings.fullscreen)),
+            format!("Frame limit: {}", settings.frame_limit.map_or("None".to_string(), |fps| fps.to_string())),
+            format!("Music volume: {}", percent(settings.music_volume)),
+            format!("Sound volume: {}", percent(settings.sound_volume)),
+        ]
+    }
+
+    // the size from the settings, or whatever the window is if they don't pick one
+    fn window_size(world: &World, settings: &Settings) -> (u32, u32) {
+        settings.window_size
+            .or_else(|| world
+                .try_fetch::<ScreenDimensions>()
+                .map(|screen| (screen.width() as u32, screen.height() as u32)))
+            .unwrap_or(RESOLUTIONS[1])
+    }
+
+    fn show(&mut self, world: &mut World) {
+        let mut items = OptionsMenu::labels(world).into_iter().map(MenuItem::new).collect::<Vec<MenuItem>>();
+        items.push(MenuItem::new("Key bindings"));
+        items.push(MenuItem::new("Back"));
+        self.menu = Some(Menu::new(world, "Options", items));
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.delete(world);
+        }
+    }
+
+    fn adjust(&mut self, world: &mut World, index: usize, direction: i32) {
+        // steps through a list of choices, wrapping at either end
+        fn step<T: PartialEq + Copy>(choices: &[T], current: T, direction: i32) -> T {
+            let count = choices.len() as i32;
+            let position = choices.iter().position(|choice| *choice == current).map_or(0, |position| position as i32);
+            choices[((position + direction + count) % count) as usize]
+        }
+        let volume = |volume: f32| (volume + direction as f32 * VOLUME_STEP).max(0.0).min(1.0);
+
+        {
+            let current_size = OptionsMenu::window_size(world, &world.read_resource::<Settings>());
+            let mut settings = world.write_resource::<Settings>();
+            match index {
+                RESOLUTION => settings.window_size = Some(step(&RESOLUTIONS, current_size, direction)),
+                FULLSCREEN => settings.fullscreen = !settings.fullscreen,
+                FRAME_LIMIT => settings.frame_limit = step(&FRAME_LIMITS, settings.frame_limit, direction),
+                MUSIC_VOLUME => settings.music_volume = volume(settings.music_volume),
+                SOUND_VOLUME => settings.sound_volume = volume(settings.sound_volume),
+                _ => return,
+            }
+            settings.save();
+        }
+
+        // volumes are picked up by whatever plays sound, the rest has to be pushed to the window
+        if index < MUSIC_VOLUME {
+            let settings = world.read_resource::<Settings>().clone();
+            settings.apply_to_window(world);
+        }
+        for (index, label) in OptionsMenu::labels(world).into_iter().enumerate() {
+            if let Some(menu) = &mut self.menu {
+                menu.set_label(world, index, label);
+            }
+        }
+    }
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for OptionsMenu {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.hide(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<GameData<'a, 'b>>) {
+        self.show(data.world);
+    }
+
+    fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        data.data.update(&data.world);
+
+        let choice = self.menu.as_mut().and_then(|menu| menu.update(data.world));
+        match choice {
+            Some(MenuChoice::Picked(KEY_BINDINGS)) => Trans::Push(Box::new(RebindMenu::default())),
+            // picking a setting moves it along one, the same as pressing right
+            Some(MenuChoice::Picked(index)) if index < KEY_BINDINGS => {
+                self.adjust(data.world, index, 1);
+                Trans::None
+            },
+            Some(MenuChoice::Adjust(index, direction)) => {
+                self.adjust(data.world, index, direction);
+                Trans::None
+            },
+            Some(MenuChoice::Picked(_)) | Some(MenuChoice::Back) => Trans::Pop,
+            None => Trans::None,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Locations of the game's own files, and of files that belong to the player rather than the game
+use std::{
+    env,
+    path::{Component, Path, PathBuf}
+};
+use amethyst::utils::application_root_dir;
+
+/// Where the game's files live, resolved once at startup so nothing depends on the working directory
+#[derive(Debug, Clone)]
+pub struct GamePaths {
+    pub root: PathBuf,
+    pub assets: PathBuf,
+    pub resources: PathBuf,
+}
+
+impl GamePaths {
+    pub fn new(root: PathBuf) -> Self {
+        GamePaths {
+            assets: root.join("assets"),
+            resources: root.join("resources"),
+            root,
+        }
+    }
+
+    pub fn from_application_root() -> amethyst::Result<Self> {
+        Ok(GamePaths::new(application_root_dir()?))
+    }
+
+    pub fn with_resources_dir(mut self, resources: &Path) -> Self {
+        self.resources = absolute(resources);
+        self
+    }
+
+    pub fn resource(&self, name: &str) -> PathBuf {
+        resolve(&self.resources, name)
+    }
+}
+
+/// Joins a path written in a data file onto the directory it's relative to.
+/// Either kind of slash is accepted and `..` is collapsed, since maps get edited on every platform.
+pub fn resolve(base_dir: &Path, relative: &str) -> PathBuf {
+    let relative = relative.replace('\\', "/");
+    let mut resolved = PathBuf::new();
+    for component in base_dir.join(relative).components() {
+        match component {
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    resolved.push("..");
+                }
+            },
+            Component::CurDir => {},
+            other => resolved.push(other.as_os_str()),
+        }
+    }
+    resolved
+}
+
+// paths given on the command line are relative to where the game was launched from
+pub fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir()
+            .map(|dir| resolve(&dir, &path.to_string_lossy()))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+pub fn user_config_dir() -> PathBuf {
+    env::var_os("APPDATA")
+        .or_else(|| env::var_os("XDG_CONFIG_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ld45")
+}
+
+pub fn user_bindings_path() -> PathBuf {
+    user_config_dir().join("input.ron")
+}
+
+pub fn user_settings_path() -> PathBuf {
+    user_config_dir().join("settings.ron")
+}
+
+pub fn user_save_path(slot: usize) -> PathBuf {
+    user_config_dir().join("saves").join(format!("slot{}.ron", slot + 1))
+}
@@ -0,0 +1,197 @@
+use std::fs;
+use amethyst::{
+    assets::Loader,
+    config::Config,
+    ecs::prelude::{Builder, Entity, World, WorldExt},
+    input::{get_key, Bindings, Button, InputHandler},
+    prelude::{GameData, State, StateData, StateEvent, Trans},
+    ui::{Anchor, FontHandle, TtfFormat, UiText, UiTransform},
+    winit::{ElementState, VirtualKeyCode}
+};
+use crate::{
+    bindings::{self, ActionBinding, GameBindings},
+    paths
+};
+
+const LINE_HEIGHT: f32 = 40.;
+const NORMAL_COLOUR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const SELECTED_COLOUR: [f32; 4] = [0.8, 0.1, 0.1, 1.0];
+
+/// Lets the player pick an action and press the key they want for it.
+/// Changes apply straight away and are saved to the user bindings file.
+#[derive(Default)]
+pub struct RebindMenu {
+    lines: Vec<Entity>,
+    selected: usize,
+    listening: bool,
+}
+
+impl RebindMenu {
+    fn refresh(&self, world: &mut World) {
+        let input = world.read_resource::<InputHandler<GameBindings>>();
+        let mut text_storage = world.write_storage::<UiText>();
+        for (index, (line, action)) in self.lines.iter().zip(ActionBinding::all()).enumerate() {
+            if let Some(text) = text_storage.get_mut(*line) {
+                let selected = index == self.selected;
+                text.text = if selected && self.listening {
+                    format!("{}: press a key...", action)
+                } else {
+                    format!("{}: {}", action, bindings::describe_action(&input.bindings, *action))
+                };
+                text.color = if selected { SELECTED_COLOUR } else { NORMAL_COLOUR };
+            }
+        }
+    }
+
+    fn rebind(&self, world: &mut World, key: VirtualKeyCode) {
+        let action = ActionBinding::all()[self.selected];
+        let key_combo = vec![Button::Key(key)];
+        let is_key = |combo: &Vec<Button>| combo.iter().any(|button| match button {
+            Button::Key(_) => true,
+            _ => false
+        });
+
+        let changes = {
+            let input = world.read_resource::<InputHandler<GameBindings>>();
+            let current = bindings::combos(&input.bindings, action);
+
+            // controller buttons stay as they are, only the keyboard binding is replaced
+            let mut buttons = vec![key_combo.clone()];
+            buttons.extend(current.iter().filter(|combo| !is_key(combo)).cloned());
+            let mut changes = vec![(action, buttons)];
+
+            // a key that's already taken swaps over, so the other action gets this one's old keys
+            if let Some(other) = bindings::action_bound_to(&input.bindings, &key_combo).filter(|other| *other != action) {
+                let mut other_buttons = bindings::combos(&input.bindings, other)
+                    .into_iter()
+                    .filter(|combo| *combo != key_combo)
+                    .collect::<Vec<Vec<Button>>>();
+                other_buttons.extend(current.into_iter().filter(|combo| is_key(combo)));
+                changes.push((other, other_buttons));
+            }
+            changes
+        };
+
+        // refused if the key drives an axis, e.g. the arrow keys
+        if let Err(e) = bindings::set_bindings(&mut world.write_resource::<InputHandler<GameBindings>>().bindings, changes.clone()) {
+            log::warn!("Could not bind {:?} to {:?}: {:?}", key, action, e);
+            return;
+        }
+
+        // only the actions the player changed go in their file, the rest keep following the defaults
+        let user_path = paths::user_bindings_path();
+        let mut user_bindings = if user_path.exists() {
+            match Bindings::<GameBindings>::load_no_fallback(&user_path) {
+                Ok(user_bindings) => user_bindings,
+                Err(e) => {
+                    // leave the file alone so nothing in it gets lost, the change only lasts until quitting
+                    log::error!("Not saving bindings, {:?} could not be read: {}", user_path, e);
+                    return;
+                }
+            }
+        } else {
+            Bindings::default()
+        };
+        if let Err(e) = bindings::set_bindings(&mut user_bindings, changes) {
+            log::error!("Not saving bindings, they clash with {:?}: {:?}", user_path, e);
+            return;
+        }
+
+        if let Some(dir) = user_path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                log::error!("Could not create {:?}: {}", dir, e);
+            }
+        }
+        if let Err(e) = user_bindings.write(&user_path) {
+            log::error!("Could not save bindings to {:?}: {}", user_path, e);
+        }
+    }
+}
+
+fn create_line(world: &mut World, font: &FontHandle, index: usize) -> Entity {
+    let transform = UiTransform::new(
+        format!("rebind line {}", index),
+        Anchor::Middle,
+        Anchor::Middle,
+        0.,
+        150. - index as f32 * LINE_HEIGHT,
+        1.,
+        800.,
+        LINE_HEIGHT,
+    );
+
+    world
+        .create_entity()
+        .with(transform)
+        .with(UiText::new(
+            font.clone(),
+            "".to_string(),
+            NORMAL_COLOUR,
+            30.,
+        ))
+        .build()
+}
+
+impl<'a, 'b> State<GameData<'a, 'b>, StateEvent> for RebindMenu {
+    fn on_start(&mut self, data: StateData<GameData<'a, 'b>>) {
+        let StateData { world, .. } = data;
+
+        let font = world.read_resource::<Loader>().load(
+            "font/TestFont.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        self.lines = (0..ActionBinding::all().len())
+            .map(|index| create_line(world, &font, index))
+            .collect();
+        self.selected = 0;
+        self.listening = false;
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<GameData<'a, 'b>>) {
+        if let Err(e) = data.world.delete_entities(&self.lines) {
+            log::error!("Failed to clean up rebinding screen: {}", e);
+        }
+        self.lines.clear();
+    }
+
+    fn handle_event(&mut self, data: StateData<GameData<'a, 'b>>, event: StateEvent) -> Trans<GameData<'a, 'b>, StateEvent> {
+        let key = match &event {
+            StateEvent::Window(event) => match get_key(event) {
+                Some((key, ElementState::Pressed)) => key,
+                _ => return Trans::None
+            },
+            _ => return Trans::None
+        };
+
+        if self.listening {
+            self.listening = false;
+            if key != VirtualKeyCode::Escape {
+                self.rebind(data.world, key);
+            }
+        } else {
+            match key {
+                VirtualKeyCode::Up => {
+                    self.selected = (self.selected + ActionBinding::all().len() - 1) % ActionBinding::all().len();
+                },
+                VirtualKeyCode::Down => {
+                    self.selected = (self.selected + 1) % ActionBinding::all().len();
+                },
+                VirtualKeyCode::Return => self.listening = true,
+                VirtualKeyCode::Escape => return Trans::Pop,
+                _ => {}
+            }
+        }
+
+        self.refresh(data.world);
+        Trans::None
+    }
+
+    fn update(&mut self, data: StateData<GameData<'a, 'b>>) -> Trans<GameData<'a, 'b>, StateEvent> {
+        data.data.update(&data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,114 @@
+//! Recording the player's input every tick, and feeding it back in for replays
+use std::{
+    fs,
+    path::PathBuf
+};
+use amethyst::config::Config;
+use serde::{Serialize, Deserialize};
+use crate::bindings::ActionBinding;
+
+/// Everything gameplay systems are allowed to know about the input for one tick.
+/// Systems read this rather than the `InputHandler` so that replays can stand in for the player.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub delta_seconds: f32,
+    pub horizontal: f32,
+    pub vertical: f32,
+    pub actions: Vec<ActionBinding>,
+}
+
+impl InputFrame {
+    pub fn is_down(&self, action: ActionBinding) -> bool {
+        self.actions.contains(&action)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub frames: Vec<InputFrame>,
+}
+
+impl Recording {
+    pub fn from_file(path: &PathBuf) -> Result<Self, amethyst::Error> {
+        Ok(Recording::load_no_fallback(path)?)
+    }
+}
+
+pub enum InputMode {
+    Live,
+    Recording {
+        path: PathBuf,
+        recording: Recording,
+    },
+    Replay {
+        recording: Recording,
+        next_frame: usize,
+    },
+}
+
+impl Default for InputMode {
+    fn default() -> Self {
+        InputMode::Live
+    }
+}
+
+impl InputMode {
+    pub fn record(path: PathBuf, seed: u64) -> Self {
+        InputMode::Recording {
+            path,
+            recording: Recording {
+                seed,
+                frames: Vec::new(),
+            },
+        }
+    }
+
+    pub fn replay(recording: Recording) -> Self {
+        InputMode::Replay {
+            recording,
+            next_frame: 0,
+        }
+    }
+
+    // the seed the simulation should use, if it has to match a recording
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            InputMode::Live => None,
+            InputMode::Recording { recording, .. } | InputMode::Replay { recording, .. } => Some(recording.seed),
+        }
+    }
+
+    // next recorded frame when replaying, None means read the live input instead
+    pub fn next_replay_frame(&mut self) -> Option<InputFrame> {
+        if let InputMode::Replay { recording, next_frame } = self {
+            if let Some(frame) = recording.frames.get(*next_frame) {
+                *next_frame += 1;
+                return Some(frame.clone());
+            }
+            log::info!("Replay finished after {} frames, handing control back", next_frame);
+            *self = InputMode::Live;
+        }
+        None
+    }
+
+    pub fn record_frame(&mut self, frame: &InputFrame) {
+        if let InputMode::Recording { recording, .. } = self {
+            recording.frames.push(frame.clone());
+        }
+    }
+
+    pub fn save(&self) {
+        if let InputMode::Recording { path, recording } = self {
+            if let Some(dir) = path.parent() {
+                if let Err(e) = fs::create_dir_all(dir) {
+                    log::error!("Could not create {:?}: {}", dir, e);
+                }
+            }
+            match recording.write(path) {
+                Ok(()) => log::info!("Saved {} frames of input to {:?}", recording.frames.len(), path),
+                Err(e) => log::error!("Could not save input recording to {:?}: {}", path, e),
+            }
+        }
+    }
+}
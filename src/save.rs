@@ -0,0 +1,382 @@
+//! Saved games, one RON file per slot in the player's config directory.
+//! A save records progress rather than a snapshot: loading builds the level from scratch, leaves out
+//! the food that was already picked up, and puts the player back at their last checkpoint.
+use std::{
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH}
+};
+use amethyst::{
+    config::{Config, ConfigError},
+    core::{
+        math::Vector2,
+        transform::Transform
+    },
+    ecs::prelude::{Entity, Join, World, WorldExt}
+};
+use serde::{Serialize, Deserialize};
+use crate::{
+    collision_world::CollisionWorld,
+    components::{self, FoodType},
+    level_descriptor::LevelDescriptor,
+    paths::{self, GamePaths},
+    systems::death::Lives,
+    tile_map::TileMap
+};
+
+pub const SAVE_SLOTS: usize = 3;
+// bump this when SaveGame changes in a way serde defaults can't cover, and add a step to MIGRATIONS
+pub const SAVE_VERSION: u32 = 1;
+
+// MIGRATIONS[n] turns a version n + 1 save into a version n + 2 one
+const MIGRATIONS: [fn(SaveGame) -> SaveGame; SAVE_VERSION as usize - 1] = [];
+
+/// The level being played, so a save knows what to load back into
+pub struct CurrentLevel(pub PathBuf);
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Config {
+        path: PathBuf,
+        error: ConfigError,
+    },
+    // either from a newer version of the game or not a version at all
+    UnknownVersion(u32),
+    NoLevel,
+    // the player would come back with no HP
+    Dying,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io { path, error } => write!(f, "Could not write {}: {}", path.display(), error),
+            SaveError::Config { path, error } => write!(f, "Could not use save file {}: {}", path.display(), error),
+            SaveError::UnknownVersion(version) => write!(f, "Don't know how to read save version {}", version),
+            SaveError::NoLevel => write!(f, "There's no level running to save"),
+            SaveError::Dying => write!(f, "Can't save while the player is dying"),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveGame {
+    pub version: u32,
+    // seconds since the epoch, so the title screen can continue the newest save
+    pub saved_at: u64,
+    // relative to the resources directory when the level is in there
+    pub level: PathBuf,
+    pub level_name: String,
+    pub hp: u32,
+    pub hp_max: u32,
+    pub power: u32,
+    pub belly: u8,
+    pub lives: u32,
+    pub inventory: Vec<Option<FoodType>>,
+    pub selected_slot: usize,
+    // object ids from the level data
+    pub checkpoints_reached: Vec<u32>,
+    pub last_checkpoint: Option<u32>,
+    pub collected: Vec<u32>,
+}
+
+// only used for fields an older save doesn't have, so these are what a fresh level starts with
+impl Default for SaveGame {
+    fn default() -> Self {
+        SaveGame {
+            version: SAVE_VERSION,
+            saved_at: 0,
+            level: PathBuf::new(),
+            level_name: String::new(),
+            hp: 30,
+            hp_max: 30,
+            power: 1,
+            belly: 0,
+            lives: 3,
+            inventory: Vec::new(),
+            selected_slot: 0,
+            checkpoints_reached: Vec::new(),
+            last_checkpoint: None,
+            collected: Vec::new(),
+        }
+    }
+}
+
+impl SaveGame {
+    /// Records the level that's running, as it is right now
+    pub fn capture(world: &World) -> Result<Self, SaveError> {
+        let level = world.try_fetch::<CurrentLevel>().ok_or(SaveError::NoLevel)?.0.clone();
+        let level = match world.try_fetch::<GamePaths>() {
+            Some(game_paths) => level.strip_prefix(&game_paths.resources).map(Path::to_path_buf).unwrap_or(level),
+            None => level,
+        };
+        let level_name = world.try_fetch::<LevelDescriptor>().map(|descriptor| descriptor.name.clone()).unwrap_or_default();
+        let (checkpoints_reached, last_checkpoint) = checkpoint_progress(world);
+        let lives = world.try_fetch::<Lives>().map_or(0, |lives| lives.remaining);
+        let saved_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+
+        let player_storage = world.read_storage::<components::Player>();
+        let hp_storage = world.read_storage::<components::HP>();
+        let power_storage = world.read_storage::<components::Power>();
+        let inventory_storage = world.read_storage::<components::Inventory>();
+        let (player, hp, power, inventory) = (&player_storage, &hp_storage, &power_storage, &inventory_storage)
+            .join()
+            .next()
+            .ok_or(SaveError::NoLevel)?;
+        if let components::PlayerState::Dying = player.state {
+            return Err(SaveError::Dying);
+        }
+
+        Ok(SaveGame {
+            version: SAVE_VERSION,
+            saved_at,
+            level,
+            level_name,
+            hp: hp.value,
+            hp_max: hp.max,
+            power: power.value,
+            belly: player.belly,
+            lives,
+            inventory: inventory.slots().to_vec(),
+            selected_slot: inventory.selected_index(),
+            checkpoints_reached,
+            last_checkpoint,
+            collected: collected_pickups(world),
+        })
+    }
+
+    pub fn write_slot(&self, slot: usize) -> Result<(), SaveError> {
+        let path = paths::user_save_path(slot);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|error| SaveError::Io { path: dir.to_path_buf(), error })?;
+        }
+        self.write(&path).map_err(|error| SaveError::Config { path, error })
+    }
+
+    /// None when nothing has been saved in the slot yet
+    pub fn read_slot(slot: usize) -> Option<Result<Self, SaveError>> {
+        let path = paths::user_save_path(slot);
+        if !path.exists() {
+            return None;
+        }
+        Some(SaveGame::read(&path))
+    }
+
+    pub fn read(path: &Path) -> Result<Self, SaveError> {
+        SaveGame::load_no_fallback(path)
+            .map_err(|error| SaveError::Config { path: path.to_path_buf(), error })
+            .and_then(SaveGame::migrate)
+    }
+
+    // the slot with the most recent save that can still be read
+    pub fn newest_slot() -> Option<usize> {
+        (0..SAVE_SLOTS)
+            .filter_map(|slot| match SaveGame::read_slot(slot) {
+                Some(Ok(save)) => Some((slot, save.saved_at)),
+                _ => None
+            })
+            .max_by_key(|(_, saved_at)| *saved_at)
+            .map(|(slot, _)| slot)
+    }
+
+    // older saves are read with defaults for anything they're missing, then brought up to date a version at a time
+    fn migrate(mut self) -> Result<Self, SaveError> {
+        if self.version == 0 || self.version > SAVE_VERSION {
+            return Err(SaveError::UnknownVersion(self.version));
+        }
+        while self.version < SAVE_VERSION {
+            let version = self.version;
+            self = MIGRATIONS[version as usize - 1](self);
+            self.version = version + 1;
+        }
+        Ok(self)
+    }
+
+    pub fn level_path(&self, game_paths: &GamePaths) -> PathBuf {
+        paths::resolve(&game_paths.resources, &self.level.to_string_lossy())
+    }
+
+    // a line for the save slot menus
+    pub fn describe(&self) -> String {
+        let name = if self.level_name.is_empty() { self.level.to_string_lossy().into_owned() } else { self.level_name.clone() };
+        format!("{}, HP {}/{}", name, self.hp, self.hp_max)
+    }
+
+    /// Puts the player back how they were, once the level has been built.
+    /// Collected food is left out while building, see `Level1::from_save`.
+    pub fn apply(&self, world: &mut World) {
+        let checkpoint = restore_checkpoints(world, &self.checkpoints_reached, self.last_checkpoint);
+        let checkpoint_position = checkpoint
+            .and_then(|checkpoint| world.read_storage::<Transform>().get(checkpoint)
+                .map(|transform| Vector2::new(transform.translation().x, transform.translation().y)));
+
+        {
+            let mut player_storage = world.write_storage::<components::Player>();
+            let mut hp_storage = world.write_storage::<components::HP>();
+            let mut power_storage = world.write_storage::<components::Power>();
+            let mut inventory_storage = world.write_storage::<components::Inventory>();
+            let mut transform_storage = world.write_storage::<Transform>();
+            let collider_storage = world.read_storage::<components::Collider>();
+            let mut collision_world = world.write_resource::<CollisionWorld>();
+
+            for (player, hp, power, inventory, transform, collider) in (
+                &mut player_storage,
+                &mut hp_storage,
+                &mut power_storage,
+                &mut inventory_storage,
+                &mut transform_storage,
+                &collider_storage
+            ).join() {
+                hp.max = self.hp_max;
+                hp.value = self.hp.min(self.hp_max);
+                power.value = self.power;
+                player.belly = self.belly.min(player.belly_max);
+                inventory.restore(&self.inventory, self.selected_slot);
+                if let Some(position) = checkpoint_position {
+                    collision_world.teleport(transform, collider, position);
+                }
+            }
+        }
+
+        world.insert(Lives { remaining: self.lives });
+    }
+}
+
+/// Ids of the checkpoints the player has touched, and of the one they'd respawn at
+pub fn checkpoint_progress(world: &World) -> (Vec<u32>, Option<u32>) {
+    let player_storage = world.read_storage::<components::Player>();
+    let checkpoint_storage = world.read_storage::<components::Checkpoint>();
+    let reached = (&checkpoint_storage)
+        .join()
+        .filter(|checkpoint| checkpoint.reached)
+        .map(|checkpoint| checkpoint.id)
+        .collect();
+    let last = (&player_storage)
+        .join()
+        .filter_map(|player| player.last_checkpoint)
+        .filter_map(|checkpoint| checkpoint_storage.get(checkpoint).map(|checkpoint| checkpoint.id))
+        .next();
+    (reached, last)
+}
+
+/// Marks checkpoints by id after they've been spawned again, and points the player at the last one.
+/// Returns the entity for the last checkpoint if it's still in the level.
+pub fn restore_checkpoints(world: &mut World, reached: &[u32], last: Option<u32>) -> Option<Entity> {
+    let checkpoint = {
+        let entities = world.entities();
+        let mut checkpoint_storage = world.write_storage::<components::Checkpoint>();
+        let mut last_entity = None;
+        for (entity, checkpoint) in (&entities, &mut checkpoint_storage).join() {
+            checkpoint.reached = reached.contains(&checkpoint.id);
+            if Some(checkpoint.id) == last {
+                last_entity = Some(entity);
+            }
+        }
+        last_entity
+    };
+    for player in (&mut world.write_storage::<components::Player>()).join() {
+        player.last_checkpoint = checkpoint;
+    }
+    checkpoint
+}
+
+/// Ids of the food objects in the level data that are no longer lying around
+pub fn collected_pickups(world: &World) -> Vec<u32> {
+    let tile_map = match world.try_fetch::<TileMap>() {
+        Some(tile_map) => tile_map,
+        None => return Vec::new(),
+    };
+    let pickup_storage = world.read_storage::<components::Pickup>();
+    let remaining = (&pickup_storage).join().map(|pickup| pickup.id).collect::<Vec<u32>>();
+    tile_map.objects()
+        .filter(|object| object.obj_type == "food" && !remaining.contains(&object.id))
+        .map(|object| object.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("ld45_save_tests");
+        fs::create_dir_all(&dir).expect("Failed to create scratch directory");
+        dir.join(name)
+    }
+
+    #[test]
+    fn saves_read_back_the_same() {
+        let path = scratch_path("round_trip.ron");
+        let save = SaveGame {
+            saved_at: 1_571_000_000,
+            level: PathBuf::from("level1.ron"),
+            level_name: "Meadow".to_string(),
+            hp: 12,
+            power: 4,
+            belly: 7,
+            lives: 2,
+            inventory: vec![Some(FoodType::Apple), None, Some(FoodType::Carrot)],
+            selected_slot: 2,
+            checkpoints_reached: vec![1, 2],
+            last_checkpoint: Some(2),
+            collected: vec![3],
+            ..Default::default()
+        };
+        save.write(&path).expect("Failed to write save");
+
+        let loaded = SaveGame::read(&path).expect("Failed to read save");
+
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert_eq!(loaded.level, save.level);
+        assert_eq!(loaded.level_name, save.level_name);
+        assert_eq!((loaded.hp, loaded.hp_max, loaded.power, loaded.belly, loaded.lives), (12, 30, 4, 7, 2));
+        assert_eq!(loaded.inventory, save.inventory);
+        assert_eq!(loaded.selected_slot, 2);
+        assert_eq!(loaded.checkpoints_reached, vec![1, 2]);
+        assert_eq!(loaded.last_checkpoint, Some(2));
+        assert_eq!(loaded.collected, vec![3]);
+    }
+
+    #[test]
+    fn version_one_saves_load_with_defaults_for_missing_fields() {
+        let path = scratch_path("version_one.ron");
+        fs::write(&path, r#"(
+    version: 1,
+    saved_at: 1571000000,
+    level: "level1.ron",
+    hp: 20,
+    belly: 3,
+    collected: [4],
+)"#).expect("Failed to write save");
+
+        let loaded = SaveGame::read(&path).expect("Failed to read save");
+
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert_eq!((loaded.hp, loaded.belly), (20, 3));
+        assert_eq!(loaded.collected, vec![4]);
+        assert_eq!(loaded.hp_max, SaveGame::default().hp_max);
+        assert_eq!(loaded.lives, SaveGame::default().lives);
+        assert!(loaded.inventory.is_empty());
+    }
+
+    #[test]
+    fn saves_from_newer_versions_are_refused() {
+        let path = scratch_path("newer_version.ron");
+        fs::write(&path, format!("(version: {})", SAVE_VERSION + 1)).expect("Failed to write save");
+
+        match SaveGame::read(&path) {
+            Err(SaveError::UnknownVersion(version)) => assert_eq!(version, SAVE_VERSION + 1),
+            other => panic!("Expected an unknown version error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! The player's own settings, saved in their config directory and layered over `display.ron` at startup.
+//! Key bindings are kept in their own file next to it, see `paths::user_bindings_path`.
+use std::{
+    fs,
+    time::Duration
+};
+use amethyst::{
+    config::Config,
+    core::frame_limiter::{FrameLimiter, FrameRateLimitStrategy},
+    ecs::prelude::World,
+    window::{DisplayConfig, Window},
+    winit::dpi::LogicalSize
+};
+use serde::{Serialize, Deserialize};
+use crate::{
+    cli::DEFAULT_FRAME_LIMIT,
+    paths
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // None keeps the size from display.ron
+    pub window_size: Option<(u32, u32)>,
+    pub fullscreen: bool,
+    // None runs as fast as it can
+    pub frame_limit: Option<u32>,
+    // between 0 and 1
+    pub music_volume: f32,
+    pub sound_volume: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            window_size: None,
+            fullscreen: false,
+            frame_limit: Some(DEFAULT_FRAME_LIMIT),
+            music_volume: 0.8,
+            sound_volume: 0.8,
+        }
+    }
+}
+
+impl Settings {
+    // a missing file just means nothing has been changed yet
+    pub fn load_user() -> Self {
+        let path = paths::user_settings_path();
+        if !path.exists() {
+            return Settings::default();
+        }
+        Settings::load_no_fallback(&path).unwrap_or_else(|e| {
+            log::error!("Ignoring user settings at {:?}: {}", path, e);
+            Settings::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let path = paths::user_settings_path();
+        if let Some(dir) = path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                log::error!("Could not create {:?}: {}", dir, e);
+            }
+        }
+        if let Err(e) = self.write(&path) {
+            log::error!("Could not save settings to {:?}: {}", path, e);
+        }
+    }
+
+    pub fn apply_to_display(&self, display_config: &mut DisplayConfig) {
+        if let Some(dimensions) = self.window_size {
+            display_config.dimensions = Some(dimensions);
+        }
+    }
+
+    /// Changes the open window and frame limiter to match, for settings changed while playing.
+    pub fn apply_to_window(&self, world: &World) {
+        if let Some(window) = world.try_fetch::<Window>() {
+            if let Some((width, height)) = self.window_size {
+                window.set_inner_size(LogicalSize::new(f64::from(width), f64::from(height)));
+            }
+        }
+        self.apply_fullscreen(world);
+        if let Some(mut frame_limiter) = world.try_fetch_mut::<FrameLimiter>() {
+            let (strategy, fps) = frame_rate(self.frame_limit);
+            frame_limiter.set_rate(strategy, fps);
+        }
+    }
+
+    /// Only fullscreen, for startup. Picking a monitor needs the window, and the rest was already
+    /// set up in main where command line flags can override it.
+    pub fn apply_fullscreen(&self, world: &World) {
+        if let Some(window) = world.try_fetch::<Window>() {
+            window.set_fullscreen(if self.fullscreen { Some(window.get_primary_monitor()) } else { None });
+        }
+    }
+}
+
+// how the application should cap frames for a limit, None being uncapped
+pub fn frame_rate(frame_limit: Option<u32>) -> (FrameRateLimitStrategy, u32) {
+    match frame_limit {
+        Some(fps) => (FrameRateLimitStrategy::SleepAndYield(Duration::from_millis(2)), fps),
+        None => (FrameRateLimitStrategy::Unlimited, 0),
+    }
+}
@@ -1,7 +1,6 @@
 use amethyst::{
     animation::{get_animation_set, AnimationSet, AnimationControlSet, AnimationCommand, EndControl},
-    input::{InputHandler, StringBindings},
-    ecs::prelude::{Entities, Join, Read, ReadStorage, System, WriteStorage},
+    ecs::prelude::{Entities, Join, ReadStorage, System, WriteStorage},
     renderer::SpriteRender
 };
 use crate::{
@@ -15,16 +14,25 @@ impl<'s> System<'s> for AnimationSystem {
     type SystemData = (
         ReadStorage<'s, AnimationSet<level1::AnimationId, SpriteRender>>,
         WriteStorage<'s, AnimationControlSet<level1::AnimationId, SpriteRender>>,
+        ReadStorage<'s, components::Player>,
         Entities<'s>
     );
 
-    fn run(&mut self, (animation_set_storage, mut control_set_storage, entities): Self::SystemData) {
-        for (entity, animation_set) in (&entities, &animation_set_storage).join() {
+    fn run(&mut self, (animation_set_storage, mut control_set_storage, player_storage, entities): Self::SystemData) {
+        for (entity, animation_set, player) in (&entities, &animation_set_storage, &player_storage).join() {
+            let (current, end, previous) = match player.state {
+                components::PlayerState::Dying =>
+                    (level1::AnimationId::Dying, EndControl::Stay, level1::AnimationId::IdleRight),
+                _ =>
+                    (level1::AnimationId::IdleRight, EndControl::Loop(None), level1::AnimationId::Dying),
+            };
+
             let control_set = get_animation_set(&mut control_set_storage, entity).unwrap();
+            control_set.abort(previous);
             control_set.add_animation(
-                level1::AnimationId::IdleRight,
-                &animation_set.get(&level1::AnimationId::IdleRight).unwrap(),
-                EndControl::Loop(None),
+                current,
+                &animation_set.get(&current).unwrap(),
+                end,
                 1.0,
                 AnimationCommand::Start,
             );
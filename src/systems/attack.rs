@@ -0,0 +1,154 @@
+use amethyst::{
+    core::{
+        math::Vector2,
+        transform::Transform
+    },
+    ecs::prelude::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage},
+    shrev::EventChannel
+};
+use crate::{
+    bindings::ActionBinding,
+    collision_world::CollisionWorld,
+    components,
+    input_buffer::{InputBuffer, InputBufferConfig},
+    recording::InputFrame,
+    systems::{
+        audio::SoundEvent,
+        popups::PopupEvent
+    }
+};
+
+// seconds each swing in a chain lasts
+const ATTACK_DURATION: f32 = 0.25;
+// how far in front of the player a swing lands, and how far above or below
+const ATTACK_REACH: f32 = 48.0;
+const ATTACK_HEIGHT: f32 = 24.0;
+
+pub struct AttackSystem;
+
+impl<'s> System<'s> for AttackSystem {
+    type SystemData = (
+        WriteStorage<'s, components::Player>,
+        ReadStorage<'s, components::Power>,
+        ReadStorage<'s, components::StatusEffects>,
+        ReadStorage<'s, components::Enemy>,
+        WriteStorage<'s, components::HP>,
+        ReadStorage<'s, components::Collider>,
+        ReadStorage<'s, Transform>,
+        Write<'s, CollisionWorld>,
+        Write<'s, InputBuffer>,
+        ReadExpect<'s, InputBufferConfig>,
+        Read<'s, InputFrame>,
+        Write<'s, EventChannel<PopupEvent>>,
+        Write<'s, EventChannel<SoundEvent>>,
+        Entities<'s>,
+    );
+
+    fn run(&mut self, (
+        mut player_storage,
+        power_storage,
+        status_effects_storage,
+        enemy_storage,
+        mut hp_storage,
+        collider_storage,
+        transform_storage,
+        mut collision_world,
+        mut input_buffer,
+        input_buffer_config,
+        input,
+        mut popup_events,
+        mut sound_events,
+        entities
+    ): Self::SystemData) {
+        for (mut player, power, status_effects, transform) in (
+            &mut player_storage,
+            &power_storage,
+            &status_effects_storage,
+            &transform_storage
+        ).join() {
+            match player.state {
+                components::PlayerState::Dying | components::PlayerState::Exiting => continue,
+                components::PlayerState::Attacking => {
+                    player.attack_timer -= input.delta_seconds;
+                    if player.attack_timer > 0.0 {
+                        continue;
+                    }
+
+                    // a press during the swing is still buffered, so it carries on the chain
+                    if input_buffer.consume(ActionBinding::Attack) {
+                        let step = input_buffer
+                            .matched_combo(&input_buffer_config)
+                            .map_or(1, |combo| combo.sequence.len() as u8);
+                        player.attack_chain = step;
+                        player.attack_timer = ATTACK_DURATION;
+                        if step as usize >= longest_combo(&input_buffer_config) {
+                            input_buffer.clear_history();
+                        }
+                    } else {
+                        player.attack_chain = 0;
+                        player.state = components::PlayerState::Idle;
+                        continue;
+                    }
+                },
+                _ => {
+                    if input_buffer.consume(ActionBinding::Attack) {
+                        player.attack_chain = 1;
+                        player.attack_timer = ATTACK_DURATION;
+                        player.state = components::PlayerState::Attacking;
+                    } else {
+                        continue;
+                    }
+                }
+            }
+
+            // a swing just started, later swings in the chain hit harder
+            let position = Vector2::new(transform.translation().x, transform.translation().y);
+            let damage = status_effects.effective_power(power) * u32::from(player.attack_chain);
+            let mut killed = Vec::new();
+            for (entity, _, hp, enemy_transform) in (&entities, &enemy_storage, &mut hp_storage, &transform_storage).join() {
+                let enemy_position = Vector2::new(enemy_transform.translation().x, enemy_transform.translation().y);
+                if !in_reach(position, player.facing, enemy_position) {
+                    continue;
+                }
+
+                hp.value = hp.value.saturating_sub(damage);
+                popup_events.single_write(PopupEvent::damage(enemy_position, damage));
+                sound_events.single_write(SoundEvent::Hit);
+                if hp.value == 0 {
+                    killed.push(entity);
+                }
+            }
+
+            for entity in killed {
+                remove_enemy(entity, &collider_storage, &mut collision_world, &entities);
+            }
+        }
+    }
+}
+
+fn in_reach(position: Vector2<f32>, facing: f32, target: Vector2<f32>) -> bool {
+    let ahead = (target.x - position.x) * facing;
+    ahead >= 0.0 && ahead <= ATTACK_REACH && (target.y - position.y).abs() <= ATTACK_HEIGHT
+}
+
+fn remove_enemy(
+    entity: Entity,
+    collider_storage: &ReadStorage<components::Collider>,
+    collision_world: &mut CollisionWorld,
+    entities: &Entities
+) {
+    if let Some(collider) = collider_storage.get(entity) {
+        collision_world.remove(collider);
+    }
+    if let Err(e) = entities.delete(entity) {
+        log::error!("Failed to delete defeated enemy: {}", e);
+    }
+}
+
+fn longest_combo(config: &InputBufferConfig) -> usize {
+    config.combos
+        .iter()
+        .map(|combo| combo.sequence.len())
+        .max()
+        .unwrap_or(1)
+}
@@ -0,0 +1,163 @@
+//! Sound effects for gameplay events, and the background music with a crossfade between tracks.
+//! Both are game-wide so music carries on through menus. Without an audio device they stay quiet.
+use std::collections::HashMap;
+use amethyst::{
+    assets::{AssetStorage, Loader},
+    audio::{
+        output::{init_output, Output},
+        AudioSink, Source, SourceHandle, WavFormat
+    },
+    core::timing::Time,
+    ecs::prelude::{Read, ReadExpect, System, SystemData, World, WorldExt},
+    shrev::{EventChannel, ReaderId}
+};
+use crate::settings::Settings;
+
+// seconds for one track to fade out while the next fades in
+const CROSSFADE_SECONDS: f32 = 1.5;
+
+/// Send these through the `EventChannel<SoundEvent>` resource to play a sound effect
+#[derive(Eq, PartialEq, Hash, Debug, Copy, Clone)]
+pub enum SoundEvent {
+    Eat,
+    Hit,
+    Exit,
+}
+
+impl SoundEvent {
+    fn all() -> &'static [SoundEvent] {
+        &[SoundEvent::Eat, SoundEvent::Hit, SoundEvent::Exit]
+    }
+
+    // under assets/
+    fn path(self) -> &'static str {
+        match self {
+            SoundEvent::Eat => "audio/eat.wav",
+            SoundEvent::Hit => "audio/hit.wav",
+            SoundEvent::Exit => "audio/exit.wav",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SoundSystem {
+    reader: Option<ReaderId<SoundEvent>>,
+    sounds: HashMap<SoundEvent, SourceHandle>,
+}
+
+impl<'s> System<'s> for SoundSystem {
+    type SystemData = (
+        Read<'s, EventChannel<SoundEvent>>,
+        Read<'s, AssetStorage<Source>>,
+        // missing when there's no audio device
+        Option<Read<'s, Output>>,
+        Read<'s, Settings>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        // only logs an error when there's nothing to play through
+        init_output(world);
+        self.reader = Some(world.fetch_mut::<EventChannel<SoundEvent>>().register_reader());
+
+        let loader = world.read_resource::<Loader>();
+        for sound in SoundEvent::all() {
+            let handle = loader.load(sound.path(), WavFormat, (), &world.read_resource());
+            self.sounds.insert(*sound, handle);
+        }
+    }
+
+    fn run(&mut self, (events, sources, output, settings): Self::SystemData) {
+        // read the events either way, so they don't pile up for when a device turns up
+        let events = events.read(self.reader.as_mut().expect("SoundSystem wasn't set up"));
+        let output = match output {
+            Some(output) => output,
+            None => return,
+        };
+        for event in events {
+            if let Some(source) = self.sounds.get(event).and_then(|handle| sources.get(handle)) {
+                output.play_once(source, settings.sound_volume);
+            }
+        }
+    }
+}
+
+/// The track that should be playing, set by whichever state is in charge.
+/// None fades out to silence.
+#[derive(Default)]
+pub struct Music {
+    // under assets/
+    pub track: Option<String>,
+}
+
+struct Track {
+    path: String,
+    handle: SourceHandle,
+    sink: AudioSink,
+    // how far through fading in, from 0 to 1
+    fade: f32,
+    // don't keep trying a track that can't be decoded
+    failed: bool,
+}
+
+#[derive(Default)]
+pub struct MusicSystem {
+    playing: Option<Track>,
+    fading_out: Vec<Track>,
+}
+
+impl<'s> System<'s> for MusicSystem {
+    type SystemData = (
+        Read<'s, Music>,
+        Read<'s, AssetStorage<Source>>,
+        ReadExpect<'s, Loader>,
+        Option<Read<'s, Output>>,
+        Read<'s, Settings>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (music, sources, loader, output, settings, time): Self::SystemData) {
+        let output = match output {
+            Some(output) => output,
+            None => return,
+        };
+
+        if self.playing.as_ref().map(|track| &track.path) != music.track.as_ref() {
+            if let Some(track) = self.playing.take() {
+                self.fading_out.push(track);
+            }
+            self.playing = music.track.as_ref().map(|path| Track {
+                path: path.clone(),
+                handle: loader.load(path.as_str(), WavFormat, (), &sources),
+                sink: AudioSink::new(&output),
+                fade: 0.0,
+                failed: false,
+            });
+        }
+
+        let step = time.delta_seconds() / CROSSFADE_SECONDS;
+        for track in &mut self.fading_out {
+            track.fade -= step;
+        }
+        // dropping a sink stops it
+        self.fading_out.retain(|track| track.fade > 0.0);
+        for track in &mut self.fading_out {
+            track.sink.set_volume(track.fade * settings.music_volume);
+        }
+
+        if let Some(track) = &mut self.playing {
+            track.fade = (track.fade + step).min(1.0);
+            track.sink.set_volume(track.fade * settings.music_volume);
+
+            // queue it up again whenever it runs out, so it loops
+            if track.sink.empty() && !track.failed {
+                if let Some(source) = sources.get(&track.handle) {
+                    if let Err(e) = track.sink.append(source) {
+                        log::error!("Could not play {}: {}", track.path, e);
+                        track.failed = true;
+                    }
+                }
+            }
+        }
+    }
+}
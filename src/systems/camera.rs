@@ -0,0 +1,88 @@
+use amethyst::{
+    core::{
+        math::Vector2,
+        timing::Time,
+        transform::Transform
+    },
+    ecs::prelude::{Join, Read, ReadExpect, ReadStorage, System, WriteStorage},
+    window::ScreenDimensions
+};
+use crate::{
+    components,
+    config::CameraConfig,
+    tile_map::TileMap
+};
+
+pub struct CameraSystem;
+
+impl CameraSystem {
+    // keeps the view inside the map, or centred on it when the map is smaller than the view
+    fn clamp(center: f32, half_view: f32, min: f32, max: f32) -> f32 {
+        if max - min <= half_view * 2.0 {
+            (min + max) / 2.0
+        } else {
+            center.max(min + half_view).min(max - half_view)
+        }
+    }
+}
+
+impl<'s> System<'s> for CameraSystem {
+    type SystemData = (
+        ReadStorage<'s, components::Player>,
+        WriteStorage<'s, components::CameraFollow>,
+        WriteStorage<'s, Transform>,
+        ReadExpect<'s, CameraConfig>,
+        ReadExpect<'s, ScreenDimensions>,
+        Option<ReadExpect<'s, TileMap>>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (
+        player_storage,
+        mut camera_follow_storage,
+        mut transform_storage,
+        camera_config,
+        screen_dimensions,
+        tile_map,
+        time
+    ): Self::SystemData) {
+        let player = (&player_storage, &transform_storage)
+            .join()
+            .map(|(player, transform)| (
+                Vector2::new(transform.translation().x, transform.translation().y),
+                player.facing
+            ))
+            .next();
+        let (player_position, facing) = match player {
+            Some(player) => player,
+            None => return,
+        };
+
+        let (deadzone_x, deadzone_y) = camera_config.deadzone;
+        // framerate independent easing
+        let blend = 1.0 - (-camera_config.smoothing * time.delta_seconds()).exp();
+
+        for (camera_follow, transform) in (&mut camera_follow_storage, &mut transform_storage).join() {
+            // the focus only gets dragged along once the player leaves the deadzone around it
+            let offset = player_position - camera_follow.focus;
+            camera_follow.focus.x += offset.x - offset.x.max(-deadzone_x).min(deadzone_x);
+            camera_follow.focus.y += offset.y - offset.y.max(-deadzone_y).min(deadzone_y);
+
+            camera_follow.look_ahead += (facing * camera_config.look_ahead - camera_follow.look_ahead) * blend;
+
+            let target = Vector2::new(camera_follow.focus.x + camera_follow.look_ahead, camera_follow.focus.y);
+            let mut position = camera_follow.position;
+            position += (target - position) * blend;
+
+            if let Some(tile_map) = &tile_map {
+                let (min, max) = tile_map.world_bounds();
+                position.x = Self::clamp(position.x, screen_dimensions.width() / 2.0, min.x, max.x);
+                position.y = Self::clamp(position.y, screen_dimensions.height() / 2.0, min.y, max.y);
+            }
+
+            camera_follow.position = position;
+            transform.set_translation_x(position.x);
+            transform.set_translation_y(position.y);
+        }
+    }
+}
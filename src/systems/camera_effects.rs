@@ -0,0 +1,213 @@
+use amethyst::{
+    core::{
+        math::{Vector2, Vector3},
+        timing::Time,
+        transform::Transform
+    },
+    ecs::prelude::{Component, DenseVecStorage, Entity, Join, Read, ReadStorage, System, SystemData, World, WriteExpect, WriteStorage},
+    shrev::{EventChannel, ReaderId},
+    ui::UiImage
+};
+use crate::{
+    components,
+    util::Rng
+};
+
+// how far and how much the camera can move at full trauma
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+const MAX_SHAKE_ROLL: f32 = 0.05;
+// trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+// how quickly zoom eases in and out
+const ZOOM_SMOOTHING: f32 = 10.0;
+
+/// Anything can send these through the `EventChannel<CameraEvent>` resource to get the camera to react
+#[derive(Debug, Clone)]
+pub enum CameraEvent {
+    // adds trauma between 0 and 1, shake grows with the square of it
+    Shake(f32),
+    // zooms to `scale` (2.0 is twice as close) for `duration` seconds, then back out
+    Zoom {
+        scale: f32,
+        duration: f32,
+    },
+    // moves over to `target` in `duration` seconds, stays for `hold`, then moves back to the player
+    PanTo {
+        target: Vector2<f32>,
+        duration: f32,
+        hold: f32,
+    },
+    // fades the overlay to `alpha`, 1.0 being black
+    Fade {
+        alpha: f32,
+        duration: f32,
+    },
+}
+
+struct Pan {
+    target: Vector2<f32>,
+    duration: f32,
+    hold: f32,
+    elapsed: f32,
+}
+
+impl Pan {
+    // 0 at the player, 1 at the target
+    fn weight(&self) -> f32 {
+        let duration = self.duration.max(std::f32::EPSILON);
+        let t = if self.elapsed < self.duration {
+            self.elapsed / duration
+        } else if self.elapsed < self.duration + self.hold {
+            1.0
+        } else {
+            1.0 - (self.elapsed - self.duration - self.hold) / duration
+        };
+        let t = t.max(0.0).min(1.0);
+        // smoothstep, so moves ease in and out
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    fn finished(&self) -> bool {
+        self.elapsed >= self.duration * 2.0 + self.hold
+    }
+}
+
+struct Fade {
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+/// Effect state for the follow camera, layered over the position the follow system picks
+pub struct CameraEffects {
+    trauma: f32,
+    zoom: f32,
+    zoom_target: f32,
+    zoom_timer: f32,
+    pan: Option<Pan>,
+    fade: Fade,
+    overlay: Option<Entity>,
+}
+
+impl CameraEffects {
+    pub fn new(overlay: Option<Entity>) -> Self {
+        CameraEffects {
+            trauma: 0.0,
+            zoom: 1.0,
+            zoom_target: 1.0,
+            zoom_timer: 0.0,
+            pan: None,
+            fade: Fade { from: 0.0, to: 0.0, duration: 0.0, elapsed: 0.0 },
+            overlay,
+        }
+    }
+
+    fn apply(&mut self, event: &CameraEvent) {
+        match event {
+            CameraEvent::Shake(trauma) => self.trauma = (self.trauma + trauma).min(1.0),
+            CameraEvent::Zoom { scale, duration } => {
+                self.zoom_target = *scale;
+                self.zoom_timer = *duration;
+            },
+            CameraEvent::PanTo { target, duration, hold } => {
+                self.pan = Some(Pan { target: *target, duration: *duration, hold: *hold, elapsed: 0.0 });
+            },
+            CameraEvent::Fade { alpha, duration } => {
+                self.fade = Fade { from: self.fade_alpha(), to: *alpha, duration: *duration, elapsed: 0.0 };
+            },
+        }
+    }
+
+    pub fn fade_alpha(&self) -> f32 {
+        if self.fade.elapsed >= self.fade.duration {
+            self.fade.to
+        } else {
+            self.fade.from + (self.fade.to - self.fade.from) * self.fade.elapsed / self.fade.duration
+        }
+    }
+}
+
+impl Component for CameraEffects {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(Default)]
+pub struct CameraEffectsSystem {
+    reader: Option<ReaderId<CameraEvent>>,
+}
+
+impl<'s> System<'s> for CameraEffectsSystem {
+    type SystemData = (
+        Read<'s, EventChannel<CameraEvent>>,
+        ReadStorage<'s, components::CameraFollow>,
+        WriteStorage<'s, CameraEffects>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, UiImage>,
+        WriteExpect<'s, Rng>,
+        Read<'s, Time>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(world.fetch_mut::<EventChannel<CameraEvent>>().register_reader());
+    }
+
+    fn run(&mut self, (
+        events,
+        camera_follow_storage,
+        mut camera_effects_storage,
+        mut transform_storage,
+        mut image_storage,
+        mut rng,
+        time
+    ): Self::SystemData) {
+        let delta_seconds = time.delta_seconds();
+        let events = events
+            .read(self.reader.as_mut().expect("CameraEffectsSystem wasn't set up"))
+            .cloned()
+            .collect::<Vec<CameraEvent>>();
+
+        for (camera_follow, effects, transform) in (
+            &camera_follow_storage,
+            &mut camera_effects_storage,
+            &mut transform_storage
+        ).join() {
+            for event in &events {
+                effects.apply(event);
+            }
+
+            let mut position = camera_follow.position;
+
+            if let Some(pan) = &mut effects.pan {
+                pan.elapsed += delta_seconds;
+                position += (pan.target - position) * pan.weight();
+            }
+            if effects.pan.as_ref().map_or(false, Pan::finished) {
+                effects.pan = None;
+            }
+
+            let shake = effects.trauma * effects.trauma;
+            position.x += MAX_SHAKE_OFFSET * shake * rng.next_signed();
+            position.y += MAX_SHAKE_OFFSET * shake * rng.next_signed();
+            transform.set_rotation_2d(MAX_SHAKE_ROLL * shake * rng.next_signed());
+            effects.trauma = (effects.trauma - TRAUMA_DECAY * delta_seconds).max(0.0);
+
+            effects.zoom_timer -= delta_seconds;
+            let zoom_target = if effects.zoom_timer > 0.0 { effects.zoom_target } else { 1.0 };
+            effects.zoom += (zoom_target - effects.zoom) * (1.0 - (-ZOOM_SMOOTHING * delta_seconds).exp());
+            // a smaller camera transform shows less of the world, which reads as zooming in
+            transform.set_scale(Vector3::new(1.0 / effects.zoom, 1.0 / effects.zoom, 1.0));
+
+            transform.set_translation_x(position.x);
+            transform.set_translation_y(position.y);
+
+            effects.fade.elapsed += delta_seconds;
+            if let Some(overlay) = effects.overlay {
+                if let Some(image) = image_storage.get_mut(overlay) {
+                    *image = UiImage::SolidColor([0.0, 0.0, 0.0, effects.fade_alpha()]);
+                }
+            }
+        }
+    }
+}
@@ -1,50 +1,84 @@
 use amethyst::{
-    core::{
-        math::Vector2,
-        timing::Time,
-        transform::Transform
-    },
-    ecs::prelude::{Entities, Join, Read, Write, ReadStorage, System, WriteStorage},
+    core::math::Vector2,
+    ecs::prelude::{Entities, Join, ReadExpect, Write, ReadStorage, System, WriteStorage},
+    shrev::EventChannel
 };
-use nalgebra as na;
-use ncollide2d as nc;
 use ncollide2d::pipeline::narrow_phase::ContactEvent;
 use crate::{
     collision_world::*,
-    components
+    components,
+    food::FoodTable,
+    level1::EXIT_FADE_SECONDS,
+    systems::{
+        audio::SoundEvent,
+        camera_effects::CameraEvent,
+        popups::{PopupEvent, BELLY_COLOR}
+    }
 };
 
+// falling faster than this onto something shakes the camera
+const LANDING_SPEED: f32 = 200.0;
+
 pub struct CollisionSystem;
 
 impl<'s> System<'s> for CollisionSystem {
     type SystemData = (
         WriteStorage<'s, components::Player>,
         WriteStorage<'s, components::Power>,
+        WriteStorage<'s, components::HP>,
         WriteStorage<'s, components::Motion>,
+        WriteStorage<'s, components::StatusEffects>,
+        WriteStorage<'s, components::Inventory>,
+        WriteStorage<'s, components::Checkpoint>,
         Write<'s, CollisionWorld>,
         ReadStorage<'s, components::Food>,
+        ReadStorage<'s, components::Enemy>,
+        ReadExpect<'s, FoodTable>,
+        Write<'s, EventChannel<CameraEvent>>,
+        Write<'s, EventChannel<PopupEvent>>,
+        Write<'s, EventChannel<SoundEvent>>,
         Entities<'s>
     );
 
     fn run(&mut self, (
         mut player_storage,
         mut power_storage,
+        mut hp_storage,
         mut motion_storage,
+        mut status_effects_storage,
+        mut inventory_storage,
+        mut checkpoint_storage,
         mut collision_world,
         food_storage,
+        enemy_storage,
+        food_table,
+        mut camera_events,
+        mut popup_events,
+        mut sound_events,
         entities
     ): Self::SystemData) {
         for (
             mut player,
             mut power,
-            mut motion,
+            mut hp,
+            motion,
+            mut status_effects,
+            inventory,
         ) in (
             &mut player_storage,
             &mut power_storage,
+            &mut hp_storage,
             &mut motion_storage,
+            &mut status_effects_storage,
+            &mut inventory_storage,
         ).join() {
             collision_world.update();
 
+            // dead guinea pigs don't eat
+            if let components::PlayerState::Dying = player.state {
+                continue;
+            }
+
             let ncollide_world = &mut collision_world.world;
 
             let mut deleted_handle = None;
@@ -63,31 +97,87 @@ impl<'s> System<'s> for CollisionSystem {
                                 .collision_object(slab_handle2)
                                 .expect("Invalid collision object");
 
+                            // things bump into each other too, like a snake sat on a checkpoint,
+                            // so only pairs with the player in them count, with the player first
+                            let (slab_handle2, collision_obj1, collision_obj2) = if collision_obj1
+                                .collision_groups()
+                                .is_member_of(PLAYER_COLLISION_GROUP)
+                            {
+                                (slab_handle2, collision_obj1, collision_obj2)
+                            } else if collision_obj2.collision_groups().is_member_of(PLAYER_COLLISION_GROUP) {
+                                (slab_handle1, collision_obj2, collision_obj1)
+                            } else {
+                                continue;
+                            };
+
                             if collision_obj2.collision_groups().is_member_of(WALL_COLLISION_GROUP) {
                                 let vec1 = collision_obj1.position().translation.vector;
                                 let vec2 = collision_obj2.position().translation.vector;
                                 player.snapback = vec2 - vec1;
+                                if motion.velocity.y < -LANDING_SPEED {
+                                    camera_events.single_write(CameraEvent::Shake(0.3));
+                                }
                             }
 
                             if collision_obj2.collision_groups().is_member_of(FOOD_COLLISION_GROUP) {
                                 let food_entity = collision_obj2.data();
                                 let food = food_storage.get(*food_entity);
-                                let fillingness = food.unwrap().fillingness();
-                                if player.belly + fillingness <= player.belly_max() {
-                                    player.belly += fillingness;
-                                    power.value += 1;
-                                    deleted_handle = Some(slab_handle2.clone());
+                                let food_type = food.unwrap().food_type();
+                                let effect = food_table.effect(food_type);
+                                let food_position = collision_obj2.position().translation.vector;
+                                // too full to eat it now, so save it for later if there's room
+                                if effect.fits_in(&player) {
+                                    effect.apply(&mut player, &mut hp, &mut power, &mut status_effects);
+                                    popup_events.iter_write(PopupEvent::eaten(food_position, food_type, &effect));
+                                    sound_events.single_write(SoundEvent::Eat);
+                                    deleted_handle = Some(slab_handle2);
+                                    entities.delete(*food_entity).unwrap();
+                                } else if inventory.add(food_type) {
+                                    popup_events.single_write(
+                                        PopupEvent::new(food_position, "saved".to_string(), BELLY_COLOR).with_icon(food_type)
+                                    );
+                                    deleted_handle = Some(slab_handle2);
                                     entities.delete(*food_entity).unwrap();
                                 }
                             }
 
                             if collision_obj2.collision_groups().is_member_of(EXIT_COLLISION_GROUP) {
                                 player.state = components::PlayerState::Exiting;
+                                camera_events.single_write(CameraEvent::Fade { alpha: 1.0, duration: EXIT_FADE_SECONDS });
+                                sound_events.single_write(SoundEvent::Exit);
+                            }
+
+                            if collision_obj2.collision_groups().is_member_of(ENEMY_COLLISION_GROUP) {
+                                if let Some(enemy) = enemy_storage.get(*collision_obj2.data()) {
+                                    let damage = status_effects.damage_taken(enemy.damage);
+                                    // nothing to react to if an effect soaked up the whole hit
+                                    if damage > 0 {
+                                        hp.value = hp.value.saturating_sub(damage);
+                                        popup_events.single_write(PopupEvent::damage(collision_obj1.position().translation.vector, damage));
+                                        sound_events.single_write(SoundEvent::Hit);
+                                        camera_events.single_write(CameraEvent::Shake(0.6));
+                                        camera_events.single_write(CameraEvent::Zoom { scale: 1.1, duration: 0.2 });
+                                    }
+                                }
+                            }
+
+                            if collision_obj2.collision_groups().is_member_of(CHECKPOINT_COLLISION_GROUP) {
+                                let checkpoint = *collision_obj2.data();
+                                player.last_checkpoint = Some(checkpoint);
+                                if let Some(checkpoint) = checkpoint_storage.get_mut(checkpoint) {
+                                    checkpoint.reached = true;
+                                }
                             }
                         }
                     },
                     ContactEvent::Stopped(cobj_handle1, cobj_handle2) => {
-                        player.snapback = Vector2::new(0.0, 0.0);
+                        let involves_player = [cobj_handle1, cobj_handle2]
+                            .iter()
+                            .filter_map(|handle| ncollide_world.collision_object(**handle))
+                            .any(|collision_obj| collision_obj.collision_groups().is_member_of(PLAYER_COLLISION_GROUP));
+                        if involves_player {
+                            player.snapback = Vector2::new(0.0, 0.0);
+                        }
                     }
                 }
             }
@@ -0,0 +1,116 @@
+use amethyst::{
+    core::{
+        math::Vector2,
+        timing::Time,
+        transform::Transform
+    },
+    ecs::prelude::{Entities, Join, Read, ReadExpect, ReadStorage, System, Write, WriteExpect, WriteStorage},
+};
+use crate::{
+    collision_world::CollisionWorld,
+    components,
+    config::DeathConfig
+};
+
+pub struct Lives {
+    pub remaining: u32
+}
+
+impl Lives {
+    pub fn game_over(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+pub struct DeathSystem;
+
+impl<'s> System<'s> for DeathSystem {
+    type SystemData = (
+        WriteStorage<'s, components::Player>,
+        WriteStorage<'s, components::HP>,
+        WriteStorage<'s, components::Power>,
+        WriteStorage<'s, components::Motion>,
+        WriteStorage<'s, components::StatusEffects>,
+        WriteStorage<'s, Transform>,
+        ReadStorage<'s, components::Collider>,
+        Write<'s, CollisionWorld>,
+        WriteExpect<'s, Lives>,
+        ReadExpect<'s, DeathConfig>,
+        Read<'s, Time>,
+        Entities<'s>,
+    );
+
+    fn run(&mut self, (
+        mut player_storage,
+        mut hp_storage,
+        mut power_storage,
+        mut motion_storage,
+        mut status_effects_storage,
+        mut transform_storage,
+        collider_storage,
+        mut collision_world,
+        mut lives,
+        death_config,
+        time,
+        entities,
+    ): Self::SystemData) {
+        for (
+            entity,
+            mut player,
+            mut hp,
+            mut power,
+            mut motion,
+            status_effects,
+            collider,
+        ) in (
+            &entities,
+            &mut player_storage,
+            &mut hp_storage,
+            &mut power_storage,
+            &mut motion_storage,
+            &mut status_effects_storage,
+            &collider_storage,
+        ).join() {
+            match player.state {
+                components::PlayerState::Dying => {
+                    player.dying_timer -= time.delta_seconds();
+                    if player.dying_timer > 0.0 {
+                        continue;
+                    }
+
+                    lives.remaining = lives.remaining.saturating_sub(1);
+                    if lives.game_over() {
+                        // the level state picks this up and switches to game over
+                        continue;
+                    }
+
+                    let respawn_point = player.last_checkpoint
+                        .and_then(|checkpoint| transform_storage.get(checkpoint))
+                        .map(|transform| Vector2::new(transform.translation().x, transform.translation().y))
+                        .unwrap_or(player.spawn_point);
+
+                    if let Some(transform) = transform_storage.get_mut(entity) {
+                        collision_world.teleport(transform, collider, respawn_point);
+                    }
+
+                    hp.value = death_config.respawn_hp;
+                    power.value = death_config.power_rule.apply(power.value);
+                    player.belly = death_config.belly_rule
+                        .apply(player.belly as u32)
+                        .min(player.belly_max() as u32) as u8;
+                    status_effects.clear();
+                    player.state = components::PlayerState::Idle;
+                },
+                _ if hp.value == 0 => {
+                    player.state = components::PlayerState::Dying;
+                    player.dying_timer = death_config.dying_duration;
+                    player.lr_input_state = 0.0;
+                    player.snapback = Vector2::new(0.0, 0.0);
+                    motion.velocity = Vector2::new(0.0, 0.0);
+                    motion.acceleration = Vector2::new(0.0, 0.0);
+                },
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Draws the collision world over the level: every collider's shape and bounding box coloured by
+//! collision group, the contacts between them with their normals, and the player's velocity and snapback
+use amethyst::{
+    core::{
+        math::{Point2, Point3, Vector2, Vector3},
+        transform::Transform
+    },
+    ecs::prelude::{Join, Read, ReadStorage, System, Write},
+    renderer::{debug_drawing::DebugLines, palette::Srgba}
+};
+use ncollide2d as nc;
+use crate::{
+    collision_world::*,
+    components
+};
+
+// just in front of the level
+const Z: f32 = 0.5;
+// how far ahead the velocity line reaches, in seconds of movement
+const VELOCITY_SECONDS: f32 = 0.25;
+const NORMAL_LENGTH: f32 = 12.0;
+const CONTACT_SIZE: f32 = 3.0;
+
+const GROUP_COLORS: [(usize, [f32; 4]); 6] = [
+    (PLAYER_COLLISION_GROUP, [0.1, 0.9, 0.1, 1.0]),
+    (WALL_COLLISION_GROUP, [0.9, 0.9, 0.9, 1.0]),
+    (FOOD_COLLISION_GROUP, [1.0, 0.6, 0.0, 1.0]),
+    (EXIT_COLLISION_GROUP, [0.0, 0.9, 0.9, 1.0]),
+    (ENEMY_COLLISION_GROUP, [0.9, 0.1, 0.1, 1.0]),
+    (CHECKPOINT_COLLISION_GROUP, [0.2, 0.4, 1.0, 1.0]),
+];
+const UNKNOWN_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+const CONTACT_COLOR: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+const NORMAL_COLOR: [f32; 4] = [1.0, 0.4, 0.8, 1.0];
+const VELOCITY_COLOR: [f32; 4] = [0.4, 1.0, 0.4, 1.0];
+const SNAPBACK_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+// bounding boxes are drawn fainter, so the shape shows through where they match
+const AABB_ALPHA: f32 = 0.35;
+
+/// Whether the overlay is showing, toggled with F3 while playing
+#[derive(Default)]
+pub struct DebugDraw {
+    pub enabled: bool,
+}
+
+pub struct DebugDrawSystem;
+
+fn color([r, g, b, a]: [f32; 4]) -> Srgba {
+    Srgba::new(r, g, b, a)
+}
+
+fn point(position: Point2<f32>) -> Point3<f32> {
+    Point3::new(position.x, position.y, Z)
+}
+
+fn group_color(groups: &nc::pipeline::object::CollisionGroups) -> [f32; 4] {
+    GROUP_COLORS
+        .iter()
+        .find(|(group, _)| groups.is_member_of(*group))
+        .map_or(UNKNOWN_COLOR, |(_, color)| *color)
+}
+
+impl<'s> System<'s> for DebugDrawSystem {
+    type SystemData = (
+        Read<'s, DebugDraw>,
+        Write<'s, DebugLines>,
+        Read<'s, CollisionWorld>,
+        ReadStorage<'s, components::Player>,
+        ReadStorage<'s, components::Motion>,
+        ReadStorage<'s, Transform>,
+    );
+
+    fn run(&mut self, (
+        debug_draw,
+        mut lines,
+        collision_world,
+        player_storage,
+        motion_storage,
+        transform_storage
+    ): Self::SystemData) {
+        if !debug_draw.enabled {
+            return;
+        }
+
+        for (_, object) in collision_world.world.collision_objects() {
+            let [r, g, b, _] = group_color(object.collision_groups());
+            let aabb = object.shape().aabb(object.position());
+            lines.draw_rectangle(
+                Point2::new(aabb.mins().x, aabb.mins().y),
+                Point2::new(aabb.maxs().x, aabb.maxs().y),
+                Z,
+                color([r, g, b, AABB_ALPHA])
+            );
+
+            // everything is a cuboid for now, anything else only gets its bounding box
+            if let Some(cuboid) = object.shape().as_shape::<nc::shape::Cuboid<f32>>() {
+                let half = cuboid.half_extents();
+                let corners = [
+                    Point2::new(-half.x, -half.y),
+                    Point2::new(half.x, -half.y),
+                    Point2::new(half.x, half.y),
+                    Point2::new(-half.x, half.y),
+                ];
+                for (index, corner) in corners.iter().enumerate() {
+                    let next = corners[(index + 1) % corners.len()];
+                    lines.draw_line(
+                        point(object.position() * corner),
+                        point(object.position() * next),
+                        color([r, g, b, 1.0])
+                    );
+                }
+            }
+        }
+
+        for (_, _, _, manifold) in collision_world.world.contact_pairs(false) {
+            for tracked in manifold.contacts() {
+                let contact = &tracked.contact;
+                let at = contact.world1;
+                lines.draw_line(
+                    Point3::new(at.x - CONTACT_SIZE, at.y - CONTACT_SIZE, Z),
+                    Point3::new(at.x + CONTACT_SIZE, at.y + CONTACT_SIZE, Z),
+                    color(CONTACT_COLOR)
+                );
+                lines.draw_line(
+                    Point3::new(at.x - CONTACT_SIZE, at.y + CONTACT_SIZE, Z),
+                    Point3::new(at.x + CONTACT_SIZE, at.y - CONTACT_SIZE, Z),
+                    color(CONTACT_COLOR)
+                );
+                let normal = contact.normal.into_inner() * NORMAL_LENGTH;
+                lines.draw_direction(point(at), Vector3::new(normal.x, normal.y, 0.0), color(NORMAL_COLOR));
+            }
+        }
+
+        for (player, motion, transform) in (&player_storage, &motion_storage, &transform_storage).join() {
+            let position = Point3::new(transform.translation().x, transform.translation().y, Z);
+            let velocity: Vector2<f32> = motion.velocity * VELOCITY_SECONDS;
+            lines.draw_direction(position, Vector3::new(velocity.x, velocity.y, 0.0), color(VELOCITY_COLOR));
+            if player.snapback != Vector2::new(0.0, 0.0) {
+                lines.draw_direction(position, Vector3::new(player.snapback.x, player.snapback.y, 0.0), color(SNAPBACK_COLOR));
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use amethyst::{
+    core::{
+        math::Vector2,
+        timing::Time,
+        transform::Transform
+    },
+    ecs::prelude::{Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage},
+    shrev::EventChannel
+};
+use crate::{
+    components,
+    config::DigestionConfig,
+    systems::popups::PopupEvent
+};
+
+pub struct DigestionSystem;
+
+impl<'s> System<'s> for DigestionSystem {
+    type SystemData = (
+        WriteStorage<'s, components::Player>,
+        WriteStorage<'s, components::HP>,
+        ReadStorage<'s, Transform>,
+        ReadExpect<'s, DigestionConfig>,
+        Write<'s, EventChannel<PopupEvent>>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (
+        mut player_storage,
+        mut hp_storage,
+        transform_storage,
+        digestion_config,
+        mut popup_events,
+        time
+    ): Self::SystemData) {
+        for (mut player, mut hp, transform) in (&mut player_storage, &mut hp_storage, &transform_storage).join() {
+            if let components::PlayerState::Dying = player.state {
+                continue;
+            }
+
+            let delta_seconds = time.delta_seconds();
+
+            // running around makes you hungry
+            let rate = if player.lr_input_state != 0.0 {
+                digestion_config.exertion_multiplier
+            } else {
+                1.0
+            };
+            player.digestion += delta_seconds * rate;
+
+            while player.belly > 0 && player.digestion >= digestion_config.seconds_per_belly {
+                player.belly -= 1;
+                player.digestion -= digestion_config.seconds_per_belly;
+            }
+
+            if player.is_hungry() {
+                player.digestion = 0.0;
+                player.starving_timer += delta_seconds;
+                if player.starving_timer >= digestion_config.starving_drain_interval {
+                    player.starving_timer -= digestion_config.starving_drain_interval;
+                    hp.value = hp.value.saturating_sub(digestion_config.starving_hp_drain);
+                    popup_events.single_write(PopupEvent::damage(
+                        Vector2::new(transform.translation().x, transform.translation().y),
+                        digestion_config.starving_hp_drain
+                    ));
+                }
+            } else {
+                player.starving_timer = 0.0;
+            }
+        }
+    }
+}
@@ -1,34 +1,192 @@
 use amethyst::{
-    ecs::prelude::{Entity, Join, Read, ReadExpect, ReadStorage, System, WriteStorage},
-    ui::UiText
+    ecs::prelude::{Entity, Join, ReadExpect, ReadStorage, System, WriteStorage},
+    renderer::SpriteRender,
+    ui::{UiFinder, UiImage, UiText, UiTransform}
 };
-use crate::components;
+use crate::{
+    components::{self, FoodType},
+    level_descriptor::LevelDescriptor,
+    systems::inventory::FoodSprites
+};
+
+const BELLY_COLOR: [f32; 4] = [0.85, 0.55, 0.2, 1.0];
+const HUNGRY_COLOR: [f32; 4] = [0.6, 0.3, 0.6, 1.0];
+const SLOT_COLOR: [f32; 4] = [0.15, 0.15, 0.15, 0.8];
+const SELECTED_SLOT_COLOR: [f32; 4] = [0.9, 0.8, 0.3, 0.9];
+const EMPTY_SLOT_ICON: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+
+// everything the HUD shows, so it only gets touched when one of these changes
+#[derive(PartialEq)]
+struct HudValues {
+    level_name: String,
+    belly: u8,
+    belly_max: u8,
+    hp: u32,
+    hp_max: u32,
+    power: u32,
+    slots: Vec<Option<FoodType>>,
+    selected: usize,
+}
 
-pub struct Hud {
-    pub entity: Entity
+// widgets from assets/ui/hud.ron, looked up once the prefab has loaded
+struct HudWidgets {
+    level_name: Entity,
+    belly_fill: Entity,
+    belly_width: f32,
+    belly_label: Entity,
+    hp_fill: Entity,
+    hp_width: f32,
+    hp_label: Entity,
+    power_label: Entity,
+    // background and icon for each slot
+    slots: Vec<(Entity, Entity)>,
 }
 
-pub struct HudSystem;
+impl HudWidgets {
+    fn find(finder: &UiFinder, transform_storage: &WriteStorage<UiTransform>) -> Option<Self> {
+        let width = |entity: Entity| transform_storage.get(entity).map(|transform| transform.width);
+        let belly_fill = finder.find("belly_fill")?;
+        let hp_fill = finder.find("hp_fill")?;
+
+        let mut slots = Vec::new();
+        while let (Some(slot), Some(icon)) = (
+            finder.find(&format!("slot_{}", slots.len())),
+            finder.find(&format!("slot_{}_icon", slots.len()))
+        ) {
+            slots.push((slot, icon));
+        }
+
+        Some(HudWidgets {
+            level_name: finder.find("level_name")?,
+            belly_fill,
+            belly_width: width(belly_fill)?,
+            belly_label: finder.find("belly_label")?,
+            hp_fill,
+            hp_width: width(hp_fill)?,
+            hp_label: finder.find("hp_label")?,
+            power_label: finder.find("power_label")?,
+            slots,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct HudSystem {
+    widgets: Option<HudWidgets>,
+    shown: Option<HudValues>,
+}
 
 impl<'s> System<'s> for HudSystem {
     type SystemData = (
         ReadStorage<'s, components::Player>,
         ReadStorage<'s, components::HP>,
         ReadStorage<'s, components::Power>,
-        ReadExpect<'s, Hud>,
+        ReadStorage<'s, components::StatusEffects>,
+        ReadStorage<'s, components::Inventory>,
+        UiFinder<'s>,
         WriteStorage<'s, UiText>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiImage>,
+        Option<ReadExpect<'s, LevelDescriptor>>,
+        Option<ReadExpect<'s, FoodSprites>>,
     );
 
-    fn run(&mut self, (player_storage, hp_storage, power_storage, hud, mut text): Self::SystemData) {
-        for (player, hp, power) in (&player_storage, &hp_storage, &power_storage).join() {
-            if let Some(mut text) = text.get_mut(hud.entity) {
-                text.text = format!(
-                    "Belly: {}  HP: {}  Power: {}",
-                    player.belly.to_string(),
-                    hp.value.to_string(),
-                    power.value.to_string()
-                ).to_string();
+    fn run(&mut self, (
+        player_storage,
+        hp_storage,
+        power_storage,
+        status_effects_storage,
+        inventory_storage,
+        finder,
+        mut text_storage,
+        mut transform_storage,
+        mut image_storage,
+        descriptor,
+        food_sprites
+    ): Self::SystemData) {
+        if self.widgets.is_none() {
+            self.widgets = HudWidgets::find(&finder, &transform_storage);
+        }
+        let widgets = match &self.widgets {
+            Some(widgets) => widgets,
+            None => return,
+        };
+
+        let values = (
+            &player_storage,
+            &hp_storage,
+            &power_storage,
+            &status_effects_storage,
+            &inventory_storage
+        ).join()
+            .map(|(player, hp, power, status_effects, inventory)| HudValues {
+                level_name: descriptor.as_ref().map_or(String::new(), |descriptor| descriptor.name.clone()),
+                belly: player.belly,
+                belly_max: player.belly_max(),
+                hp: hp.value,
+                hp_max: hp.max,
+                power: status_effects.effective_power(power),
+                slots: inventory.slots().to_vec(),
+                selected: inventory.selected_index(),
+            })
+            .next();
+        let values = match values {
+            Some(values) => values,
+            None => return,
+        };
+        if self.shown.as_ref() == Some(&values) {
+            return;
+        }
+
+        let mut set_text = |entity: Entity, text: String| {
+            if let Some(ui_text) = text_storage.get_mut(entity) {
+                ui_text.text = text;
+            }
+        };
+
+        let digestion_state = if values.belly == 0 {
+            " (Hungry!)"
+        } else if values.belly == values.belly_max {
+            " (Full)"
+        } else {
+            ""
+        };
+        set_text(widgets.level_name, values.level_name.clone());
+        set_text(widgets.belly_label, format!("Belly {}/{}{}", values.belly, values.belly_max, digestion_state));
+        set_text(widgets.hp_label, format!("HP {}/{}", values.hp, values.hp_max));
+        set_text(widgets.power_label, format!("Power {}", values.power));
+
+        let fraction = |value: f32, max: f32| if max > 0.0 { (value / max).min(1.0) } else { 0.0 };
+        if let Some(transform) = transform_storage.get_mut(widgets.belly_fill) {
+            transform.width = widgets.belly_width * fraction(f32::from(values.belly), f32::from(values.belly_max));
+        }
+        if let Some(transform) = transform_storage.get_mut(widgets.hp_fill) {
+            transform.width = widgets.hp_width * fraction(values.hp as f32, values.hp_max as f32);
+        }
+        if let Some(image) = image_storage.get_mut(widgets.belly_fill) {
+            *image = UiImage::SolidColor(if values.belly == 0 { HUNGRY_COLOR } else { BELLY_COLOR });
+        }
+
+        // slots past the inventory's size stay empty
+        for (index, (slot, icon)) in widgets.slots.iter().enumerate() {
+            let color = if index == values.selected { SELECTED_SLOT_COLOR } else { SLOT_COLOR };
+            if let Some(image) = image_storage.get_mut(*slot) {
+                *image = UiImage::SolidColor(color);
+            }
+
+            let food_type = values.slots.get(index).and_then(|slot| *slot);
+            let icon_image = match (food_type, &food_sprites) {
+                (Some(food_type), Some(food_sprites)) => UiImage::Sprite(SpriteRender {
+                    sprite_sheet: food_sprites.sheet.clone(),
+                    sprite_number: food_type.sprite_number()
+                }),
+                _ => UiImage::SolidColor(EMPTY_SLOT_ICON),
+            };
+            if let Some(image) = image_storage.get_mut(*icon) {
+                *image = icon_image;
             }
         }
+
+        self.shown = Some(values);
     }
 }
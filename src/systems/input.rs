@@ -1,22 +1,89 @@
 use amethyst::{
-    input::{InputHandler, StringBindings},
-    ecs::prelude::{Join, Read, ReadStorage, System, WriteStorage}
+    core::timing::Time,
+    input::InputHandler,
+    ecs::prelude::{Join, Read, ReadExpect, System, Write, WriteStorage}
+};
+use crate::{
+    bindings::{ActionBinding, AxisBinding, GameBindings},
+    components,
+    gamepad::ControllerConfig,
+    input_buffer::{InputBuffer, InputBufferConfig},
+    recording::{InputFrame, InputMode}
 };
-use crate::components;
 
 pub struct InputSystem;
 
+impl InputSystem {
+    fn sample(input: &InputHandler<GameBindings>, controller_config: &ControllerConfig, time: &Time) -> InputFrame {
+        InputFrame {
+            delta_seconds: time.delta_seconds(),
+            horizontal: Self::axis(input, controller_config, AxisBinding::Horizontal, AxisBinding::PadHorizontal),
+            vertical: Self::axis(input, controller_config, AxisBinding::Vertical, AxisBinding::PadVertical),
+            actions: ActionBinding::all()
+                .iter()
+                .filter(|action| input.action_is_down(*action).unwrap_or(false))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    // the keyboard wins, otherwise take the first stick that's pushed past its deadzone
+    fn axis(
+        input: &InputHandler<GameBindings>,
+        controller_config: &ControllerConfig,
+        keyboard: AxisBinding,
+        pad: fn(u32) -> AxisBinding
+    ) -> f32 {
+        input.axis_value(&keyboard)
+            .filter(|direction| *direction != 0.0)
+            .or_else(|| controller_config.profiles
+                .iter()
+                .filter_map(|profile| input
+                    .axis_value(&pad(profile.controller_id))
+                    .map(|value| profile.process(value)))
+                .find(|direction| *direction != 0.0))
+            .unwrap_or(0.0)
+    }
+}
+
 impl<'s> System<'s> for InputSystem {
     type SystemData = (
         WriteStorage<'s, components::Player>,
-        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, InputHandler<GameBindings>>,
+        ReadExpect<'s, ControllerConfig>,
+        Write<'s, InputMode>,
+        Write<'s, InputFrame>,
+        Write<'s, Time>,
+        Write<'s, InputBuffer>,
+        ReadExpect<'s, InputBufferConfig>,
     );
 
-    fn run(&mut self, (mut player_storage, input): Self::SystemData) {
-        for (mut player) in (&mut player_storage).join() {
-            if let Some(direction) = input.axis_value("left_right") {
-                 player.lr_input_state = direction;
+    fn run(&mut self, (
+        mut player_storage,
+        input,
+        controller_config,
+        mut input_mode,
+        mut input_frame,
+        mut time,
+        mut input_buffer,
+        input_buffer_config
+    ): Self::SystemData) {
+        *input_frame = match input_mode.next_replay_frame() {
+            Some(frame) => {
+                // replays have to step the simulation by exactly what was recorded
+                time.set_delta_seconds(frame.delta_seconds);
+                frame
+            },
+            None => Self::sample(&input, &controller_config, &time)
+        };
+        input_mode.record_frame(&input_frame);
+        input_buffer.update(input_frame.delta_seconds, &input_frame.actions, &input_buffer_config);
+
+        for player in (&mut player_storage).join() {
+            player.lr_input_state = input_frame.horizontal;
+            if input_frame.horizontal != 0.0 {
+                player.facing = input_frame.horizontal.signum();
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,147 @@
+use amethyst::{
+    core::{
+        math::{Vector2, Vector3},
+        transform::Transform
+    },
+    ecs::prelude::{Entities, Join, ReadExpect, System, Write, WriteStorage},
+    renderer::{sprite::SpriteSheetHandle, SpriteRender},
+    shrev::EventChannel
+};
+use crate::{
+    bindings::ActionBinding,
+    collision_world::{CollisionWorld, FOOD_COLLISION_GROUP},
+    components,
+    food::FoodTable,
+    input_buffer::InputBuffer,
+    level1::{HALF_WIDTH, SPRITE_WIDTH},
+    systems::{
+        audio::SoundEvent,
+        popups::PopupEvent
+    }
+};
+
+// how far in front of the player dropped food lands, far enough that we don't pick it straight back up
+const DROP_DISTANCE: f32 = SPRITE_WIDTH * 1.5;
+
+pub struct FoodSprites {
+    pub sheet: SpriteSheetHandle
+}
+
+pub struct InventorySystem;
+
+impl<'s> System<'s> for InventorySystem {
+    type SystemData = (
+        WriteStorage<'s, components::Player>,
+        WriteStorage<'s, components::HP>,
+        WriteStorage<'s, components::Power>,
+        WriteStorage<'s, components::StatusEffects>,
+        WriteStorage<'s, components::Inventory>,
+        WriteStorage<'s, components::Food>,
+        WriteStorage<'s, components::Collider>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, SpriteRender>,
+        Write<'s, CollisionWorld>,
+        ReadExpect<'s, FoodTable>,
+        // missing when running headless
+        Option<ReadExpect<'s, FoodSprites>>,
+        Write<'s, InputBuffer>,
+        Write<'s, EventChannel<PopupEvent>>,
+        Write<'s, EventChannel<SoundEvent>>,
+        Entities<'s>,
+    );
+
+    fn run(&mut self, (
+        mut player_storage,
+        mut hp_storage,
+        mut power_storage,
+        mut status_effects_storage,
+        mut inventory_storage,
+        mut food_storage,
+        mut collider_storage,
+        mut transform_storage,
+        mut sprite_storage,
+        mut collision_world,
+        food_table,
+        food_sprites,
+        mut input_buffer,
+        mut popup_events,
+        mut sound_events,
+        entities,
+    ): Self::SystemData) {
+        let eat = input_buffer.consume(ActionBinding::Eat);
+        let drop = input_buffer.consume(ActionBinding::Drop);
+        let next = input_buffer.consume(ActionBinding::NextItem);
+
+        let mut dropped = Vec::new();
+
+        for (
+            mut player,
+            mut hp,
+            mut power,
+            mut status_effects,
+            inventory,
+            transform,
+        ) in (
+            &mut player_storage,
+            &mut hp_storage,
+            &mut power_storage,
+            &mut status_effects_storage,
+            &mut inventory_storage,
+            &transform_storage,
+        ).join() {
+            if let components::PlayerState::Dying = player.state {
+                continue;
+            }
+
+            if next {
+                inventory.select_next();
+            }
+
+            if eat {
+                if let Some(food_type) = inventory.take_selected() {
+                    let effect = food_table.effect(food_type);
+                    if effect.fits_in(&player) {
+                        effect.apply(&mut player, &mut hp, &mut power, &mut status_effects);
+                        let position = Vector2::new(transform.translation().x, transform.translation().y);
+                        popup_events.iter_write(PopupEvent::eaten(position, food_type, &effect));
+                        sound_events.single_write(SoundEvent::Eat);
+                    } else {
+                        inventory.put_back(food_type);
+                    }
+                }
+            }
+
+            if drop {
+                if let Some(food_type) = inventory.take_selected() {
+                    let position = Vector3::new(
+                        transform.translation().x + player.facing * DROP_DISTANCE,
+                        transform.translation().y,
+                        -1.0
+                    );
+                    dropped.push((food_type, position));
+                }
+            }
+        }
+
+        for (food_type, position) in dropped {
+            let food_transform = Transform::from(position);
+            let food = entities.create();
+            let collider = collision_world.add_object(
+                (HALF_WIDTH, HALF_WIDTH),
+                FOOD_COLLISION_GROUP,
+                food,
+                &food_transform
+            );
+
+            food_storage.insert(food, components::Food::new(food_type)).expect("Failed to add food to entity");
+            collider_storage.insert(food, collider).expect("Failed to add collider to entity");
+            transform_storage.insert(food, food_transform).expect("Failed to add transform to entity");
+            if let Some(food_sprites) = &food_sprites {
+                sprite_storage.insert(food, SpriteRender {
+                    sprite_sheet: food_sprites.sheet.clone(),
+                    sprite_number: food_type.sprite_number()
+                }).expect("Failed to add sprite to entity");
+            }
+        }
+    }
+}
@@ -2,4 +2,15 @@ pub mod animation;
 pub mod motion;
 pub mod collision;
 pub mod input;
-pub mod hud;
\ No newline at end of file
+pub mod hud;
+pub mod death;
+pub mod digestion;
+pub mod status_effects;
+pub mod inventory;
+pub mod attack;
+pub mod camera;
+pub mod camera_effects;
+pub mod parallax;
+pub mod popups;
+pub mod audio;
+pub mod debug_draw;
\ No newline at end of file
@@ -4,12 +4,13 @@ use amethyst::{
         timing::Time,
         transform::Transform
     },
-    ecs::prelude::{Entities, Join, Read, Write, ReadStorage, System, WriteStorage},
+    ecs::prelude::{Join, Read, ReadExpect, Write, ReadStorage, System, WriteStorage},
 };
 use nalgebra as na;
 use crate::{
     collision_world::CollisionWorld,
-    components
+    components,
+    config::DigestionConfig
 };
 
 const SPEED: f32 = 120.0;
@@ -22,8 +23,10 @@ impl<'s> System<'s> for MotionSystem {
         WriteStorage<'s, Transform>,
         WriteStorage<'s, components::Collider>,
         WriteStorage<'s, components::Motion>,
+        ReadStorage<'s, components::StatusEffects>,
         Write<'s, CollisionWorld>,
         Read<'s, Time>,
+        ReadExpect<'s, DigestionConfig>,
     );
 
     fn run(&mut self, (
@@ -31,21 +34,36 @@ impl<'s> System<'s> for MotionSystem {
         mut transform_storage,
         mut collider_storage,
         mut motion_storage,
+        status_effects_storage,
         mut collision_world,
         time,
+        digestion_config,
     ): Self::SystemData) {
         for (
-            mut player,
+            player,
             mut transform,
             mut collider,
             mut motion,
+            status_effects,
         ) in (
             &mut player_storage,
             &mut transform_storage,
             &mut collider_storage,
             &mut motion_storage,
+            &status_effects_storage,
         ).join() {
-            motion.velocity.x = player.lr_input_state * SPEED;
+            if let components::PlayerState::Dying = player.state {
+                continue;
+            }
+
+            let base_speed = if player.is_hungry() {
+                SPEED * digestion_config.hungry_speed_factor
+            } else {
+                SPEED
+            };
+            let speed = status_effects.effective(components::Stat::Speed, base_speed);
+
+            motion.velocity.x = player.lr_input_state * speed;
             if player.snapback.x != 0.0 {
                 motion.velocity = Vector2::new(-player.snapback.x, motion.velocity.y);
                 collision_world.update_position(&mut transform, &mut collider, &mut motion, &time);
@@ -0,0 +1,33 @@
+use amethyst::{
+    core::transform::Transform,
+    ecs::prelude::{Join, ReadStorage, System, WriteStorage},
+    renderer::camera::Camera
+};
+use crate::components;
+
+pub struct ParallaxSystem;
+
+impl<'s> System<'s> for ParallaxSystem {
+    type SystemData = (
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, components::Parallax>,
+        WriteStorage<'s, Transform>,
+    );
+
+    fn run(&mut self, (camera_storage, parallax_storage, mut transform_storage): Self::SystemData) {
+        let camera = (&camera_storage, &transform_storage)
+            .join()
+            .map(|(_, transform)| (transform.translation().x, transform.translation().y))
+            .next();
+        let (camera_x, camera_y) = match camera {
+            Some(camera) => camera,
+            None => return,
+        };
+
+        // a layer that scrolls at 0.3 is carried along by the other 0.7 of the camera's movement
+        for (parallax, transform) in (&parallax_storage, &mut transform_storage).join() {
+            transform.set_translation_x(parallax.origin.x + camera_x * (1.0 - parallax.scroll.x));
+            transform.set_translation_y(parallax.origin.y + camera_y * (1.0 - parallax.scroll.y));
+        }
+    }
+}
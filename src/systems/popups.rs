@@ -0,0 +1,263 @@
+use amethyst::{
+    assets::Loader,
+    core::{
+        math::Vector2,
+        timing::Time,
+        transform::Transform,
+        Hidden
+    },
+    ecs::prelude::{
+        Builder, Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System,
+        SystemData, World, WorldExt, WriteStorage
+    },
+    renderer::{camera::Camera, SpriteRender},
+    shrev::{EventChannel, ReaderId},
+    ui::{Anchor, FontHandle, TtfFormat, UiImage, UiText, UiTransform}
+};
+use crate::{
+    components::FoodType,
+    food::FoodEffect,
+    systems::inventory::FoodSprites
+};
+
+// seconds a popup stays up
+const LIFETIME: f32 = 1.0;
+// pixels per second popups float upwards
+const RISE_SPEED: f32 = 40.0;
+// popups made up front, more get made if they're all in use at once
+const POOL_SIZE: usize = 8;
+const ICON_SIZE: f32 = 24.0;
+const TEXT_WIDTH: f32 = 160.0;
+
+pub const DAMAGE_COLOR: [f32; 4] = [0.85, 0.1, 0.1, 1.0];
+pub const HEAL_COLOR: [f32; 4] = [0.1, 0.6, 0.1, 1.0];
+pub const BELLY_COLOR: [f32; 4] = [0.8, 0.45, 0.1, 1.0];
+pub const POWER_COLOR: [f32; 4] = [0.75, 0.6, 0.0, 1.0];
+
+/// Send these through the `EventChannel<PopupEvent>` resource to float some text up from a spot in the world
+#[derive(Debug, Clone)]
+pub struct PopupEvent {
+    pub position: Vector2<f32>,
+    pub text: String,
+    pub color: [f32; 4],
+    // food sprite shown next to the text
+    pub icon: Option<FoodType>,
+}
+
+impl PopupEvent {
+    pub fn new(position: Vector2<f32>, text: String, color: [f32; 4]) -> Self {
+        PopupEvent { position, text, color, icon: None }
+    }
+
+    pub fn with_icon(mut self, food_type: FoodType) -> Self {
+        self.icon = Some(food_type);
+        self
+    }
+
+    pub fn damage(position: Vector2<f32>, amount: u32) -> Self {
+        PopupEvent::new(position, format!("-{} HP", amount), DAMAGE_COLOR)
+    }
+
+    pub fn heal(position: Vector2<f32>, amount: u32) -> Self {
+        PopupEvent::new(position, format!("+{} HP", amount), HEAL_COLOR)
+    }
+
+    /// One popup per thing eating the food changed, stacked so they don't overlap
+    pub fn eaten(position: Vector2<f32>, food_type: FoodType, effect: &FoodEffect) -> Vec<Self> {
+        let signed = |value: i32| if value >= 0 { format!("+{}", value) } else { value.to_string() };
+        let mut lines = vec![(format!("+{} belly", effect.fillingness), BELLY_COLOR)];
+        if effect.heal != 0 {
+            lines.push((format!("{} HP", signed(effect.heal)), if effect.heal > 0 { HEAL_COLOR } else { DAMAGE_COLOR }));
+        }
+        if effect.power != 0 {
+            lines.push((format!("{} power", signed(effect.power)), POWER_COLOR));
+        }
+
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(index, (text, color))| {
+                let offset = Vector2::new(0.0, index as f32 * ICON_SIZE);
+                let popup = PopupEvent::new(position + offset, text, color);
+                if index == 0 { popup.with_icon(food_type) } else { popup }
+            })
+            .collect()
+    }
+}
+
+// a pooled popup, it's hidden whenever age has passed the lifetime
+pub struct Popup {
+    position: Vector2<f32>,
+    color: [f32; 4],
+    age: f32,
+    icon: Entity,
+}
+
+impl Component for Popup {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(Default)]
+pub struct PopupSystem {
+    reader: Option<ReaderId<PopupEvent>>,
+    font: Option<FontHandle>,
+}
+
+impl PopupSystem {
+    fn ui_transform(id: &str, width: f32, height: f32) -> UiTransform {
+        UiTransform::new(id.to_string(), Anchor::Middle, Anchor::Middle, 0., 0., 5., width, height)
+    }
+
+    fn text(font: FontHandle) -> UiText {
+        let mut text = UiText::new(font, String::new(), [0.0; 4], 20.);
+        text.align = Anchor::MiddleLeft;
+        text
+    }
+}
+
+impl<'s> System<'s> for PopupSystem {
+    type SystemData = (
+        Read<'s, EventChannel<PopupEvent>>,
+        WriteStorage<'s, Popup>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, UiImage>,
+        WriteStorage<'s, Hidden>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Transform>,
+        Option<ReadExpect<'s, FoodSprites>>,
+        Read<'s, Time>,
+        Entities<'s>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(world.fetch_mut::<EventChannel<PopupEvent>>().register_reader());
+        let font = world.read_resource::<Loader>().load(
+            "font/TestFont.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        for _ in 0..POOL_SIZE {
+            let icon = world
+                .create_entity()
+                .with(PopupSystem::ui_transform("popup icon", ICON_SIZE, ICON_SIZE))
+                .with(Hidden)
+                .build();
+            world
+                .create_entity()
+                .with(PopupSystem::ui_transform("popup", TEXT_WIDTH, ICON_SIZE))
+                .with(PopupSystem::text(font.clone()))
+                .with(Popup { position: Vector2::new(0.0, 0.0), color: [0.0; 4], age: LIFETIME, icon })
+                .with(Hidden)
+                .build();
+        }
+        self.font = Some(font);
+    }
+
+    fn run(&mut self, (
+        events,
+        mut popup_storage,
+        mut ui_transform_storage,
+        mut text_storage,
+        mut image_storage,
+        mut hidden_storage,
+        camera_storage,
+        transform_storage,
+        food_sprites,
+        time,
+        entities
+    ): Self::SystemData) {
+        for event in events.read(self.reader.as_mut().expect("PopupSystem wasn't set up")) {
+            let free = (&entities, &popup_storage)
+                .join()
+                .find(|(_, popup)| popup.age >= LIFETIME)
+                .map(|(entity, _)| entity);
+            let popup = match free {
+                Some(popup) => popup,
+                None => {
+                    let icon = entities
+                        .build_entity()
+                        .with(PopupSystem::ui_transform("popup icon", ICON_SIZE, ICON_SIZE), &mut ui_transform_storage)
+                        .build();
+                    let font = self.font.clone().expect("PopupSystem wasn't set up");
+                    entities
+                        .build_entity()
+                        .with(PopupSystem::ui_transform("popup", TEXT_WIDTH, ICON_SIZE), &mut ui_transform_storage)
+                        .with(PopupSystem::text(font), &mut text_storage)
+                        .with(Popup { position: Vector2::new(0.0, 0.0), color: [0.0; 4], age: LIFETIME, icon }, &mut popup_storage)
+                        .build()
+                }
+            };
+
+            let icon = {
+                let popup = popup_storage.get_mut(popup).expect("Popup went missing");
+                popup.position = event.position;
+                popup.color = event.color;
+                popup.age = 0.0;
+                popup.icon
+            };
+            if let Some(text) = text_storage.get_mut(popup) {
+                text.text = event.text.clone();
+            }
+            hidden_storage.remove(popup);
+
+            let sprite = event.icon.and_then(|food_type| food_sprites.as_ref().map(|food_sprites| SpriteRender {
+                sprite_sheet: food_sprites.sheet.clone(),
+                sprite_number: food_type.sprite_number()
+            }));
+            match sprite {
+                Some(sprite) => {
+                    image_storage.insert(icon, UiImage::Sprite(sprite)).expect("Failed to add popup icon");
+                    hidden_storage.remove(icon);
+                },
+                None => {
+                    hidden_storage.insert(icon, Hidden).expect("Failed to hide popup icon");
+                }
+            }
+        }
+
+        // popups live in the world, so follow the camera around to stay over the spot they came from
+        let camera = (&camera_storage, &transform_storage)
+            .join()
+            .map(|(_, transform)| (
+                Vector2::new(transform.translation().x, transform.translation().y),
+                transform.scale().x
+            ))
+            .next();
+        let (camera_position, camera_scale) = match camera {
+            Some(camera) => camera,
+            None => return,
+        };
+
+        let delta_seconds = time.delta_seconds();
+        for (entity, popup) in (&entities, &mut popup_storage).join() {
+            if popup.age >= LIFETIME {
+                continue;
+            }
+            popup.age += delta_seconds;
+            if popup.age >= LIFETIME {
+                hidden_storage.insert(entity, Hidden).expect("Failed to hide popup");
+                hidden_storage.insert(popup.icon, Hidden).expect("Failed to hide popup icon");
+                continue;
+            }
+
+            let screen = (popup.position + Vector2::new(0.0, RISE_SPEED * popup.age) - camera_position) / camera_scale;
+            if let Some(transform) = ui_transform_storage.get_mut(entity) {
+                transform.local_x = screen.x + TEXT_WIDTH / 2.0;
+                transform.local_y = screen.y;
+            }
+            if let Some(transform) = ui_transform_storage.get_mut(popup.icon) {
+                transform.local_x = screen.x - ICON_SIZE / 2.0;
+                transform.local_y = screen.y;
+            }
+            if let Some(text) = text_storage.get_mut(entity) {
+                let mut color = popup.color;
+                color[3] *= 1.0 - popup.age / LIFETIME;
+                text.color = color;
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use amethyst::{
+    core::{
+        math::Vector2,
+        timing::Time,
+        transform::Transform
+    },
+    ecs::prelude::{Join, Read, ReadStorage, System, Write, WriteStorage},
+    shrev::EventChannel
+};
+use crate::{
+    components::{self, TickEffect},
+    systems::popups::PopupEvent
+};
+
+pub struct StatusEffectSystem;
+
+impl<'s> System<'s> for StatusEffectSystem {
+    type SystemData = (
+        WriteStorage<'s, components::StatusEffects>,
+        WriteStorage<'s, components::HP>,
+        ReadStorage<'s, Transform>,
+        Write<'s, EventChannel<PopupEvent>>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (
+        mut status_effects_storage,
+        mut hp_storage,
+        transform_storage,
+        mut popup_events,
+        time
+    ): Self::SystemData) {
+        for (status_effects, hp, transform) in (
+            &mut status_effects_storage,
+            (&mut hp_storage).maybe(),
+            (&transform_storage).maybe()
+        ).join() {
+            let fired = status_effects.tick(time.delta_seconds());
+
+            // entities without HP can still have effects, they just can't be healed or hurt
+            if let Some(hp) = hp {
+                let position = transform.map(|transform| Vector2::new(transform.translation().x, transform.translation().y));
+                for tick_effect in fired {
+                    match tick_effect {
+                        TickEffect::Heal(amount) => {
+                            hp.heal(amount);
+                            popup_events.iter_write(position.map(|position| PopupEvent::heal(position, amount)));
+                        },
+                        TickEffect::Damage(amount) => {
+                            let damage = status_effects.damage_taken(amount);
+                            if damage > 0 {
+                                hp.value = hp.value.saturating_sub(damage);
+                                popup_events.iter_write(position.map(|position| PopupEvent::damage(position, damage)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,16 +1,11 @@
 use std::{
-    error::Error,
-    fmt,
-    fs::File,
-    path::Path
+    fs,
+    path::{Path, PathBuf}
 };
 use amethyst::{
     assets::{AssetStorage, Handle, Loader},
-    core::{
-        math::Vector3,
-        transform::Transform
-    },
-    ecs::{Entity, Builder, World, WorldExt},
+    core::math::{Vector2, Vector3},
+    ecs::{World, WorldExt},
     renderer::{
         formats::texture::ImageFormat,
         sprite::{Sprite, SpriteSheet, SpriteSheetHandle},
@@ -19,43 +14,31 @@ use amethyst::{
     tiles::{Tile, TileMap as AmethystTileMap}
 };
 use tiled;
+use crate::{
+    components::FoodType,
+    level_descriptor::ParallaxLayer,
+    load_error::LevelLoadError,
+    paths
+};
 
-// Example path: "./resources/desert.tmx"
-pub struct TmxFilePath<'a>(pub &'a str);
-
-#[derive(Debug)]
-pub struct TilesetNotFoundError;
-
-impl Error for TilesetNotFoundError {}
-
-impl fmt::Display for TilesetNotFoundError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "No tilesets were found.")
-    }
-}
-
-#[derive(Debug)]
-pub struct TilesetImageNotFoundError;
-
-impl Error for TilesetImageNotFoundError {}
-
-impl fmt::Display for TilesetImageNotFoundError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "No images for the tileset were found.")
-    }
-}
+// Example path: GamePaths::resource("desert.tmx")
+pub struct TmxFilePath<'a>(pub &'a Path);
 
 struct TileMapDimensions(u32, u32);
 struct TileDimensions(u32, u32);
 
 pub struct TileMap {
-    tiled_map: tiled::Map
+    tiled_map: tiled::Map,
+    // tileset images are relative to the map file, not to wherever the game was started
+    directory: PathBuf,
+    // the tiled crate doesn't hand over image layers, so those are read from the xml itself
+    image_layers: Vec<ParallaxLayer>
 }
 
 pub fn create_map<TileType: Tile>(
     tmx_file_path: TmxFilePath,
     world: &mut World
-) -> Result<(AmethystTileMap<TileType>, TileMap), Box<dyn Error>> {
+) -> Result<(AmethystTileMap<TileType>, TileMap), LevelLoadError> {
     let tile_map = TileMap::load_map(tmx_file_path)?;
 
     let TileMapDimensions(width, height) = tile_map.dimensions();
@@ -72,16 +55,121 @@ pub fn create_map<TileType: Tile>(
 }
 
 impl TileMap {
-    fn load_map(tmx_file_path: TmxFilePath) -> Result<Self, Box<dyn Error>> {
-        let map_file = File::open(&Path::new(&tmx_file_path.0))?;
-        let tiled_map = tiled::parse(map_file)?;
+    pub fn load_map(tmx_file_path: TmxFilePath) -> Result<Self, LevelLoadError> {
+        let path = tmx_file_path.0;
+        let source = fs::read_to_string(path)
+            .map_err(|error| LevelLoadError::Io { path: path.to_path_buf(), error })?;
+        let tiled_map = tiled::parse(source.as_bytes())
+            .map_err(|error| LevelLoadError::TmxParse { path: path.to_path_buf(), error })?;
+        let directory = tmx_file_path.0.parent().map(Path::to_path_buf).unwrap_or_default();
+        let image_layers = image_layer_blocks(&source)
+            .into_iter()
+            .filter_map(|block| {
+                let image_tag = &block[block.find("<image ")?..];
+                let image = paths::resolve(&directory, attribute(image_tag, "source")?);
+                Some(parallax_properties(|name| property(block, name), ParallaxLayer {
+                    image: image.to_string_lossy().into_owned(),
+                    ..Default::default()
+                }))
+            })
+            .collect();
         Ok(TileMap {
-            tiled_map
+            tiled_map,
+            directory,
+            image_layers
         })
     }
 
+    // the layer the level is played on, which is the first one not marked as parallax or collision
     pub fn tiles(&self) -> Option<&Vec<Vec<u32>>> {
-        self.tiled_map.layers.get(0).map(|layer| &layer.tiles)
+        self.tiled_map.layers
+            .iter()
+            .find(|layer| layer_parallax(layer).is_none() && !is_collision_layer(layer))
+            .map(|layer| &layer.tiles)
+    }
+
+    /// The tile layer marked with a `collision` property in Tiled. Every tile in it is a wall.
+    pub fn collision_tiles(&self) -> Option<&Vec<Vec<u32>>> {
+        self.tiled_map.layers
+            .iter()
+            .find(|layer| is_collision_layer(layer))
+            .map(|layer| &layer.tiles)
+    }
+
+    /// Tile layers marked with `parallax_x` or `parallax_y` properties in Tiled.
+    /// `z`, `repeat_x` and `repeat_y` properties work the same as in level descriptors.
+    pub fn parallax_tile_layers(&self) -> Vec<(ParallaxLayer, &Vec<Vec<u32>>)> {
+        self.tiled_map.layers
+            .iter()
+            .filter_map(|layer| layer_parallax(layer).map(|parallax| (parallax, &layer.tiles)))
+            .collect()
+    }
+
+    /// Image layers are always drawn as parallax backgrounds, set up by the same properties as tile layers
+    pub fn parallax_image_layers(&self) -> &[ParallaxLayer] {
+        &self.image_layers
+    }
+
+    // world position of the center of the tile at this column and row
+    pub fn tile_center(&self, column: u32, row: u32) -> Vector2<f32> {
+        let TileDimensions(tile_width, tile_height) = self.tile_dimensions();
+        self.pixel_to_world(
+            (column * tile_width) as f32 + tile_width as f32 / 2.0,
+            (row * tile_height) as f32 + tile_height as f32 / 2.0
+        )
+    }
+
+    pub fn tile_half_extents(&self) -> (f32, f32) {
+        let TileDimensions(tile_width, tile_height) = self.tile_dimensions();
+        (tile_width as f32 / 2.0, tile_height as f32 / 2.0)
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &tiled::Object> {
+        self.tiled_map.object_groups
+            .iter()
+            .flat_map(|group| group.objects.iter())
+    }
+
+    // converts Tiled pixel coordinates (origin top left, y down) into world coordinates,
+    // where tile centers sit on the tile map's grid
+    pub fn pixel_to_world(&self, x: f32, y: f32) -> Vector2<f32> {
+        let TileMapDimensions(width, height) = self.dimensions();
+        let TileDimensions(tile_width, tile_height) = self.tile_dimensions();
+        Vector2::new(
+            x - (width * tile_width) as f32 / 2.0 - tile_width as f32 / 2.0,
+            (height * tile_height) as f32 / 2.0 - y + tile_height as f32 / 2.0
+        )
+    }
+
+    // lower left and upper right corners of the map in world coordinates
+    pub fn world_bounds(&self) -> (Vector2<f32>, Vector2<f32>) {
+        let TileMapDimensions(width, height) = self.dimensions();
+        let TileDimensions(tile_width, tile_height) = self.tile_dimensions();
+        let top_left = self.pixel_to_world(0.0, 0.0);
+        let bottom_right = self.pixel_to_world((width * tile_width) as f32, (height * tile_height) as f32);
+        (Vector2::new(top_left.x, bottom_right.y), Vector2::new(bottom_right.x, top_left.y))
+    }
+
+    // world position of the center of an object
+    pub fn object_position(&self, object: &tiled::Object) -> Vector2<f32> {
+        let (width, height) = Self::object_size(object);
+        self.pixel_to_world(object.x + width / 2.0, object.y + height / 2.0)
+    }
+
+    // points and polygons don't have a box, so they're treated as zero sized
+    pub fn object_size(object: &tiled::Object) -> (f32, f32) {
+        match object.shape {
+            tiled::ObjectShape::Rect { width, height } | tiled::ObjectShape::Ellipse { width, height } => (width, height),
+            _ => (0.0, 0.0)
+        }
+    }
+
+    // which food a "food" object is, from its food property
+    pub fn object_food_type(object: &tiled::Object) -> Option<FoodType> {
+        match object.properties.get("food") {
+            Some(tiled::PropertyValue::StringValue(name)) => FoodType::from_name(name),
+            _ => None
+        }
     }
 
     fn dimensions(&self) -> TileMapDimensions {
@@ -92,26 +180,37 @@ impl TileMap {
         TileDimensions(self.tiled_map.tile_width, self.tiled_map.tile_height)
     }
 
-    fn load_tileset(&self, world: &mut World) -> Result<SpriteSheetHandle, Box<dyn Error>> {
+    pub fn load_tileset(&self, world: &mut World) -> Result<SpriteSheetHandle, LevelLoadError> {
         let tiled_image = self.get_tile_set_image()?;
-        Ok(self.load_sprite_sheet(world, tiled_image))
+        self.load_sprite_sheet(world, tiled_image)
     }
 
-    fn get_tile_set_image(&self) -> Result<&tiled::Image, Box<dyn Error>> {
+    fn get_tile_set_image(&self) -> Result<&tiled::Image, LevelLoadError> {
         // This will always use index 0 because we won't have more than 1 image per tileset
         // and we also won't have more than 1 tileset per tile map
         let tilesets = &self.tiled_map.tilesets;
-        let first_tileset = tilesets.get(0).ok_or_else(|| TilesetNotFoundError)?;
-        let first_image = first_tileset.images.get(0).ok_or_else(|| TilesetImageNotFoundError)?;
+        let first_tileset = tilesets.get(0).ok_or(LevelLoadError::MissingTileset)?;
+        let first_image = first_tileset.images.get(0).ok_or(LevelLoadError::MissingTilesetImage)?;
         Ok(first_image)
     }
 
-    fn load_sprite_sheet(&self, world: &mut World, tiled_image: &tiled::Image) -> SpriteSheetHandle {
+    // absolute path of the tileset image, the loader accepts these as well as paths under assets/
+    pub fn tileset_image_path(&self) -> Result<PathBuf, LevelLoadError> {
+        let tiled_image = self.get_tile_set_image()?;
+        Ok(paths::resolve(&self.directory, &tiled_image.source))
+    }
+
+    fn load_sprite_sheet(&self, world: &mut World, tiled_image: &tiled::Image) -> Result<SpriteSheetHandle, LevelLoadError> {
+        let image_path = paths::resolve(&self.directory, &tiled_image.source);
+        // the loader only notices a missing texture later on, so check up front
+        if !image_path.is_file() {
+            return Err(LevelLoadError::MissingAsset(image_path));
+        }
         let texture_handle: Handle<Texture> = {
             let loader = world.read_resource::<Loader>();
             let texture_storage = world.read_resource::<AssetStorage<Texture>>();
             loader.load(
-                tiled_image.source.as_str(),
+                image_path.to_string_lossy(),
                 ImageFormat::default(),
                 (),
                 &texture_storage,
@@ -120,11 +219,11 @@ impl TileMap {
 
         let sprite_sheet = self.create_sprite_sheet(texture_handle, tiled_image);
         let loader = world.read_resource::<Loader>();
-        loader.load_from_data(
+        Ok(loader.load_from_data(
             sprite_sheet,
             (),
             &world.read_resource::<AssetStorage<SpriteSheet>>(),
-        )
+        ))
     }
 
     fn create_sprite_sheet(&self, texture: Handle<Texture>, tiled_image: &tiled::Image) -> SpriteSheet {
@@ -161,3 +260,73 @@ impl TileMap {
         }
     }
 }
+
+// tile layers with a collision property ticked in Tiled are walls rather than something to draw
+fn is_collision_layer(layer: &tiled::Layer) -> bool {
+    match layer.properties.get("collision") {
+        Some(tiled::PropertyValue::BoolValue(collision)) => *collision,
+        _ => false
+    }
+}
+
+// parallax settings for a tile layer, None for layers the level is played on
+fn layer_parallax(layer: &tiled::Layer) -> Option<ParallaxLayer> {
+    let property = |name: &str| match layer.properties.get(name)? {
+        tiled::PropertyValue::BoolValue(value) => Some(value.to_string()),
+        tiled::PropertyValue::FloatValue(value) => Some(value.to_string()),
+        tiled::PropertyValue::IntValue(value) => Some(value.to_string()),
+        tiled::PropertyValue::StringValue(value) => Some(value.clone()),
+        _ => None
+    };
+    if property("parallax_x").is_none() && property("parallax_y").is_none() {
+        return None;
+    }
+    Some(parallax_properties(property, ParallaxLayer {
+        scroll: (1.0, 1.0),
+        repeat_x: false,
+        ..Default::default()
+    }))
+}
+
+// tiled 0.7 skips `<imagelayer>` elements entirely, image and properties included, so they're
+// the one thing still picked out of the xml by hand
+fn image_layer_blocks(source: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("<imagelayer ") {
+        let block = &rest[start..];
+        let end = block.find("</imagelayer>").map_or(block.len(), |end| end + "</imagelayer>".len());
+        blocks.push(&block[..end]);
+        rest = &block[end..];
+    }
+    blocks
+}
+
+fn attribute<'s>(element: &'s str, name: &str) -> Option<&'s str> {
+    let pattern = format!(" {}=\"", name);
+    let tag_end = element.find('>').unwrap_or_else(|| element.len());
+    let start = element[..tag_end].find(&pattern)? + pattern.len();
+    let length = element[start..].find('"')?;
+    Some(&element[start..start + length])
+}
+
+fn property(block: &str, name: &str) -> Option<String> {
+    block
+        .match_indices("<property ")
+        .map(|(start, _)| &block[start..])
+        .find(|element| attribute(element, "name") == Some(name))
+        .and_then(|element| attribute(element, "value"))
+        .map(str::to_string)
+}
+
+fn parallax_properties(property: impl Fn(&str) -> Option<String>, defaults: ParallaxLayer) -> ParallaxLayer {
+    let float = |name: &str, default: f32| property(name).and_then(|value| value.parse().ok()).unwrap_or(default);
+    let flag = |name: &str, default: bool| property(name).and_then(|value| value.parse().ok()).unwrap_or(default);
+    ParallaxLayer {
+        scroll: (float("parallax_x", defaults.scroll.0), float("parallax_y", defaults.scroll.1)),
+        repeat_x: flag("repeat_x", defaults.repeat_x),
+        repeat_y: flag("repeat_y", defaults.repeat_y),
+        z: float("z", defaults.z),
+        image: defaults.image,
+    }
+}
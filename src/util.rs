@@ -1,4 +1,8 @@
-/// General purpose utility types
+//! General purpose utility types
+use std::{
+    fs,
+    path::Path
+};
 
 // newtypes for making it easier to know what args should be passed into which positions
 pub struct PngPath<'a>(pub &'a str);
@@ -6,4 +10,44 @@ pub struct RonPath<'a>(pub &'a str);
 
 pub struct Exiting {
     pub exiting: bool
-}
\ No newline at end of file
+}
+
+// small xorshift generator, anything random in the simulation should come from here
+// so that replays with the same seed play out the same way
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Rng { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // between -1.0 and 1.0
+    pub fn next_signed(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
+}
+
+// reads the size out of the IHDR chunk, which always comes first in a png
+pub fn png_dimensions(path: &Path) -> Result<(u32, u32), String> {
+    let bytes = fs::read(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    if bytes.len() < 24 || &bytes[..8] != b"\x89PNG\r\n\x1a\n" || &bytes[12..16] != b"IHDR" {
+        return Err(format!("{} is not a png", path.display()));
+    }
+    let read_u32 = |start: usize| {
+        (u32::from(bytes[start]) << 24)
+            | (u32::from(bytes[start + 1]) << 16)
+            | (u32::from(bytes[start + 2]) << 8)
+            | u32::from(bytes[start + 3])
+    };
+    Ok((read_u32(16), read_u32(20)))
+}
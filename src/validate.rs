@@ -0,0 +1,635 @@
+//! Checks maps, sprite sheets, prefabs and configs up front, so mistakes in them are reported
+//! together with where they are instead of turning up one at a time while playing
+use std::{
+    fmt,
+    fs,
+    path::{Path, PathBuf}
+};
+use amethyst::{
+    config::Config,
+    window::DisplayConfig
+};
+use serde::{Serialize, Deserialize};
+use crate::{
+    bindings,
+    config::{CameraConfig, DeathConfig, DigestionConfig},
+    food::FoodTable,
+    gamepad::ControllerConfig,
+    input_buffer::InputBufferConfig,
+    level_descriptor::LevelDescriptor,
+    paths::{self, GamePaths},
+    tile_map::{TileMap, TmxFilePath},
+    util::png_dimensions
+};
+
+// tiled keeps flip flags in the top bits of each gid
+const GID_MASK: u32 = 0x1FFF_FFFF;
+
+#[derive(Debug)]
+pub struct Problem {
+    pub file: PathBuf,
+    // 1-based, None when the problem is with the file as a whole
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Problems(Vec<Problem>);
+
+impl Problems {
+    fn add<S: Into<String>>(&mut self, file: &Path, line: Option<usize>, message: S) {
+        self.0.push(Problem {
+            file: file.to_path_buf(),
+            line,
+            message: message.into(),
+        });
+    }
+}
+
+/// Validates every config in the resources directory, every level descriptor and TMX map
+/// (plus `extra_levels`, which can be either), every sprite sheet under `assets/textures`
+/// and every prefab under `assets/prefab` and `assets/ui`
+pub fn validate_assets(game_paths: &GamePaths, extra_levels: &[PathBuf]) -> Vec<Problem> {
+    let mut problems = Problems::default();
+
+    validate_configs(game_paths, &mut problems);
+
+    let mut descriptors = files_with_extension(&game_paths.resources, "ron")
+        .into_iter()
+        .filter(|path| path.file_stem().map_or(false, |stem| stem.to_string_lossy().starts_with("level")))
+        .collect::<Vec<PathBuf>>();
+    let mut maps = files_with_extension(&game_paths.resources, "tmx");
+    for level in extra_levels {
+        let levels = if level.extension().map_or(false, |ext| ext == "tmx") { &mut maps } else { &mut descriptors };
+        if !levels.contains(level) {
+            levels.push(level.clone());
+        }
+    }
+    for descriptor in &descriptors {
+        if let Some(map) = validate_level_descriptor(descriptor, &game_paths.assets, &mut problems) {
+            if !maps.contains(&map) {
+                maps.push(map);
+            }
+        }
+    }
+    for map in &maps {
+        validate_map(map, &mut problems);
+    }
+
+    let mut objects_sprite_count = None;
+    for sheet in files_with_extension(&game_paths.assets.join("textures"), "ron") {
+        let sprite_count = validate_sprite_sheet(&sheet, &mut problems);
+        if sheet.file_stem().map_or(false, |stem| stem == "objects") {
+            objects_sprite_count = sprite_count;
+        }
+    }
+    if let Some(sprite_count) = objects_sprite_count {
+        validate_food_sprites(&game_paths.resource("food.ron"), sprite_count, &mut problems);
+    }
+
+    for prefab in files_with_extension(&game_paths.assets.join("prefab"), "ron") {
+        validate_prefab(&prefab, &game_paths.assets, &mut problems);
+    }
+    for prefab in files_with_extension(&game_paths.assets.join("ui"), "ron") {
+        validate_ui_prefab(&prefab, &game_paths.assets, &mut problems);
+    }
+
+    problems.0
+}
+
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files = fs::read_dir(dir)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == extension))
+            .collect::<Vec<PathBuf>>())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+// loads every config strictly, so typos show up here instead of silently falling back to defaults
+fn validate_configs(game_paths: &GamePaths, problems: &mut Problems) {
+    let mut check = |name: &str, result: Result<(), String>| {
+        if let Err(e) = result {
+            problems.add(&game_paths.resource(name), None, e);
+        }
+    };
+
+    check("display.ron", loaded(DisplayConfig::load_no_fallback(game_paths.resource("display.ron"))));
+    check("input.ron", loaded(bindings::load_bindings(&game_paths.resource("input.ron"), &paths::user_bindings_path())));
+    check("controllers.ron", loaded(ControllerConfig::load_no_fallback(game_paths.resource("controllers.ron"))));
+    check("death.ron", loaded(DeathConfig::load_no_fallback(game_paths.resource("death.ron"))));
+    check("digestion.ron", loaded(DigestionConfig::load_no_fallback(game_paths.resource("digestion.ron"))));
+    check("food.ron", loaded(FoodTable::load_no_fallback(game_paths.resource("food.ron"))));
+    check("input_buffer.ron", loaded(InputBufferConfig::load_no_fallback(game_paths.resource("input_buffer.ron"))));
+    check("camera.ron", loaded(CameraConfig::load_no_fallback(game_paths.resource("camera.ron"))));
+}
+
+fn loaded<T, E: ToString>(result: Result<T, E>) -> Result<(), String> {
+    result.map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn validate_map(path: &Path, problems: &mut Problems) {
+    let map = match tiled::parse_file(path) {
+        Ok(map) => map,
+        Err(e) => return problems.add(path, None, format!("could not parse map: {:?}", e)),
+    };
+    let source = fs::read_to_string(path).unwrap_or_default();
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut tile_count = 0;
+    for tileset in &map.tilesets {
+        let tileset_line = find_line(&source, &format!("name=\"{}\"", tileset.name));
+        let image = match tileset.images.get(0) {
+            Some(image) => image,
+            None => {
+                problems.add(path, tileset_line, format!("tileset {:?} has no image", tileset.name));
+                continue;
+            }
+        };
+        let image_line = find_line(&source, &format!("source=\"{}\"", image.source));
+
+        let image_path = paths::resolve(directory, &image.source);
+        match png_dimensions(&image_path) {
+            Ok((width, height)) => {
+                if (width as i32, height as i32) != (image.width, image.height) {
+                    problems.add(path, image_line, format!(
+                        "tileset image is declared as {}x{} but {} is {}x{}",
+                        image.width, image.height, image_path.display(), width, height
+                    ));
+                }
+            },
+            Err(e) => problems.add(path, image_line, e),
+        }
+
+        if tileset.tile_width == 0 || tileset.tile_height == 0 {
+            problems.add(path, tileset_line, "tiles need a width and height");
+            continue;
+        }
+        let columns = image.width.max(0) as u32 / tileset.tile_width;
+        let rows = image.height.max(0) as u32 / tileset.tile_height;
+        tile_count = tile_count.max(tileset.first_gid + columns * rows - 1);
+    }
+
+    // csv data puts each row of tiles on its own line after the <data> tag
+    let data_lines = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains("<data"))
+        .map(|(index, _)| index + 1)
+        .collect::<Vec<usize>>();
+    for (layer_index, layer) in map.layers.iter().enumerate() {
+        for (row_index, row) in layer.tiles.iter().enumerate() {
+            let line = data_lines.get(layer_index).map(|data_line| data_line + row_index + 1);
+            for (column, gid) in row.iter().enumerate() {
+                let gid = gid & GID_MASK;
+                if gid > tile_count {
+                    problems.add(path, line, format!(
+                        "layer {:?} column {} uses tile {}, but the tilesets only have {} tiles",
+                        layer.name, column, gid, tile_count
+                    ));
+                }
+            }
+        }
+    }
+
+    for object in map.object_groups.iter().flat_map(|group| group.objects.iter()) {
+        let line = find_line(&source, &format!("<object id=\"{}\"", object.id));
+        match object.obj_type.as_str() {
+            "checkpoint" => {
+                let (width, height) = TileMap::object_size(object);
+                if width <= 0.0 || height <= 0.0 {
+                    problems.add(path, line, format!("checkpoint {} needs a width and height", object.id));
+                }
+            },
+            "food" => {
+                if TileMap::object_food_type(object).is_none() {
+                    problems.add(path, line, format!("food {} needs a food property naming a food type", object.id));
+                }
+            },
+            other => problems.add(path, line, format!("object {} has unknown type {:?}", object.id, other)),
+        }
+    }
+
+    // tiled doesn't look at image layers at all, they're only used as parallax backgrounds
+    if let Ok(tile_map) = TileMap::load_map(TmxFilePath(path)) {
+        for layer in tile_map.parallax_image_layers() {
+            let image_path = Path::new(&layer.image);
+            let line = image_path.file_name().and_then(|name| find_line(&source, &name.to_string_lossy()));
+            if let Err(e) = png_dimensions(image_path) {
+                problems.add(path, line, e);
+            }
+        }
+    }
+}
+
+// returns the descriptor's map, if it has one that exists
+fn validate_level_descriptor(path: &Path, assets: &Path, problems: &mut Problems) -> Option<PathBuf> {
+    let descriptor = match LevelDescriptor::load_no_fallback(path) {
+        Ok(descriptor) => descriptor,
+        Err(e) => {
+            problems.add(path, None, format!("could not parse level descriptor: {}", e));
+            return None;
+        }
+    };
+    let source = fs::read_to_string(path).unwrap_or_default();
+
+    for layer in &descriptor.parallax {
+        let line = find_line(&source, &format!("\"{}\"", layer.image));
+        if let Err(e) = png_dimensions(&assets.join(&layer.image)) {
+            problems.add(path, line, e);
+        }
+    }
+
+    if let Some(music) = &descriptor.music {
+        if !assets.join(music).is_file() {
+            problems.add(path, find_line(&source, "music:"), format!("music {} doesn't exist", music));
+        }
+    }
+
+    let map = paths::resolve(path.parent().unwrap_or_else(|| Path::new("")), &descriptor.map);
+    if map.is_file() {
+        Some(map)
+    } else {
+        problems.add(path, find_line(&source, "map:"), format!("map {} doesn't exist", map.display()));
+        None
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct SpriteRect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+// the List form of amethyst's sprite sheet files
+#[derive(Default, Serialize, Deserialize)]
+struct SpriteList {
+    texture_width: u32,
+    texture_height: u32,
+    sprites: Vec<SpriteRect>,
+}
+
+// returns how many sprites the sheet has, if it could be read
+fn validate_sprite_sheet(path: &Path, problems: &mut Problems) -> Option<usize> {
+    let sheet = match SpriteList::load_no_fallback(path) {
+        Ok(sheet) => sheet,
+        Err(e) => {
+            problems.add(path, None, format!("could not parse sprite sheet: {}", e));
+            return None;
+        }
+    };
+    let source = fs::read_to_string(path).unwrap_or_default();
+    let sprite_lines = field_lines(&source, "x");
+
+    check_texture_size(path, &path.with_extension("png"), (sheet.texture_width, sheet.texture_height), find_line(&source, "texture_width"), problems);
+    for (index, sprite) in sheet.sprites.iter().enumerate() {
+        check_sprite_bounds(path, sprite_lines.get(index).cloned(), index, sprite, (sheet.texture_width, sheet.texture_height), problems);
+    }
+
+    Some(sheet.sprites.len())
+}
+
+fn validate_food_sprites(food_path: &Path, sprite_count: usize, problems: &mut Problems) {
+    if let Ok(food_table) = FoodTable::load_no_fallback(food_path) {
+        for food_type in food_table.foods.keys() {
+            if food_type.sprite_number() >= sprite_count {
+                problems.add(food_path, None, format!(
+                    "{:?} uses sprite {}, but the objects sheet only has {} sprites",
+                    food_type, food_type.sprite_number(), sprite_count
+                ));
+            }
+        }
+    }
+}
+
+// prefabs hold amethyst types that can't be loaded without a renderer, so they're scanned line by line instead
+fn validate_prefab(path: &Path, assets_dir: &Path, problems: &mut Problems) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => return problems.add(path, None, format!("could not read prefab: {}", e)),
+    };
+    let lines = source.lines().collect::<Vec<&str>>();
+
+    let texture = lines
+        .iter()
+        .enumerate()
+        .find_map(|(index, line)| quoted_after(line, "File(").map(|file| (index + 1, assets_dir.join(file))));
+
+    let mut sprite_count = 0;
+    let mut definitions = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        if line.contains("Grid((") || line.contains("List((") {
+            let start = index;
+            // a definition ends where its brackets close
+            let mut depth = 0i32;
+            let mut end = index;
+            for (offset, line) in lines[index..].iter().enumerate() {
+                depth += line.matches('(').count() as i32 - line.matches(')').count() as i32;
+                if depth <= 0 {
+                    end = index + offset;
+                    break;
+                }
+            }
+            let block = &lines[start..=end];
+            let texture_size = (
+                block.iter().find_map(|line| field_value(line, "texture_width")).unwrap_or(0),
+                block.iter().find_map(|line| field_value(line, "texture_height")).unwrap_or(0),
+            );
+            if let Some((_, texture_path)) = &texture {
+                check_texture_size(path, texture_path, texture_size, Some(start + 1), problems);
+            }
+
+            if line.contains("Grid((") {
+                definitions.push(("Grid", start + 1));
+                let columns = block.iter().find_map(|line| field_value(line, "columns")).unwrap_or(0);
+                let rows = block.iter().find_map(|line| field_value(line, "rows")).unwrap_or(0);
+                sprite_count += (columns * rows) as usize;
+            } else {
+                definitions.push(("List", start + 1));
+                for (offset, line) in block.iter().enumerate().filter(|(_, line)| field_value::<u32>(line, "x").is_some()) {
+                    let sprite = SpriteRect {
+                        x: field_value(line, "x").unwrap_or(0),
+                        y: field_value(line, "y").unwrap_or(0),
+                        width: field_value(line, "width").unwrap_or(0),
+                        height: field_value(line, "height").unwrap_or(0),
+                    };
+                    check_sprite_bounds(path, Some(start + offset + 1), sprite_count, &sprite, texture_size, problems);
+                    sprite_count += 1;
+                }
+            }
+            index = end + 1;
+        } else {
+            index += 1;
+        }
+    }
+
+    if definitions.len() > 1 {
+        let kinds = definitions
+            .iter()
+            .map(|(kind, line)| format!("{} on line {}", kind, line))
+            .collect::<Vec<String>>();
+        problems.add(path, definitions.get(1).map(|(_, line)| *line), format!(
+            "sheet is built from {}, so sprite indices carry on from one definition to the next",
+            kinds.join(" and ")
+        ));
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        let used = match (field_value::<usize>(line, "sprite_number"), list_after(line, "output:")) {
+            (Some(sprite), _) => vec![sprite],
+            (None, Some(sprites)) => sprites,
+            (None, None) => continue,
+        };
+        for sprite in used.into_iter().filter(|sprite| *sprite >= sprite_count) {
+            problems.add(path, Some(index + 1), format!(
+                "uses sprite {}, but the sheet only has {} sprites",
+                sprite, sprite_count
+            ));
+        }
+    }
+}
+
+fn check_texture_size(file: &Path, texture_path: &Path, declared: (u32, u32), line: Option<usize>, problems: &mut Problems) {
+    match png_dimensions(texture_path) {
+        Ok(actual) => {
+            if actual != declared {
+                problems.add(file, line, format!(
+                    "texture is declared as {}x{} but {} is {}x{}",
+                    declared.0, declared.1, texture_path.display(), actual.0, actual.1
+                ));
+            }
+        },
+        Err(e) => problems.add(file, line, e),
+    }
+}
+
+fn check_sprite_bounds(
+    file: &Path,
+    line: Option<usize>,
+    index: usize,
+    sprite: &SpriteRect,
+    texture_size: (u32, u32),
+    problems: &mut Problems
+) {
+    if sprite.x + sprite.width > texture_size.0 || sprite.y + sprite.height > texture_size.1 {
+        problems.add(file, line, format!(
+            "sprite {} ({}, {}, {}x{}) goes outside the {}x{} texture",
+            index, sprite.x, sprite.y, sprite.width, sprite.height, texture_size.0, texture_size.1
+        ));
+    }
+}
+
+// ui widgets are found by id, so two with the same one means one of them never gets updated
+fn validate_ui_prefab(path: &Path, assets_dir: &Path, problems: &mut Problems) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => return problems.add(path, None, format!("could not read ui prefab: {}", e)),
+    };
+
+    let mut ids = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        if let Some(file) = quoted_after(line, "File(") {
+            if !assets_dir.join(file).is_file() {
+                problems.add(path, Some(index + 1), format!("{} doesn't exist", assets_dir.join(file).display()));
+            }
+        }
+        if let Some(id) = quoted_after(line, "id:") {
+            if ids.contains(&id) {
+                problems.add(path, Some(index + 1), format!("more than one widget has the id {:?}", id));
+            }
+            ids.push(id);
+        }
+    }
+}
+
+fn find_line(source: &str, needle: &str) -> Option<usize> {
+    source.lines().position(|line| line.contains(needle)).map(|index| index + 1)
+}
+
+// line numbers of every line that sets the given field
+fn field_lines(source: &str, field: &str) -> Vec<usize> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| field_value::<u32>(line, field).is_some())
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+// the value of `field: value` on this line, matching whole field names only
+fn field_value<T: std::str::FromStr>(line: &str, field: &str) -> Option<T> {
+    let pattern = format!("{}:", field);
+    line.match_indices(&pattern)
+        .filter(|(start, _)| {
+            line[..*start]
+                .chars()
+                .last()
+                .map_or(true, |c| !(c.is_alphanumeric() || c == '_'))
+        })
+        .find_map(|(start, _)| {
+            let value = line[start + pattern.len()..]
+                .trim_start()
+                .split(|c: char| !(c.is_alphanumeric() || c == '.' || c == '-'))
+                .next()?;
+            value.parse().ok()
+        })
+}
+
+fn quoted_after<'l>(line: &'l str, prefix: &str) -> Option<&'l str> {
+    let rest = &line[line.find(prefix)? + prefix.len()..];
+    let rest = &rest[rest.find('"')? + 1..];
+    Some(&rest[..rest.find('"')?])
+}
+
+fn list_after(line: &str, prefix: &str) -> Option<Vec<usize>> {
+    let rest = &line[line.find(prefix)? + prefix.len()..];
+    let rest = &rest[rest.find('[')? + 1..];
+    Some(rest[..rest.find(']')?]
+        .split(',')
+        .filter_map(|value| value.trim().parse().ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(relative: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+    }
+
+    // writes the file somewhere scratch and returns what the check found in it
+    fn problems_in(name: &str, contents: &str, check: impl Fn(&Path, &mut Problems)) -> Vec<Problem> {
+        let dir = std::env::temp_dir().join("ld45_validate_tests");
+        fs::create_dir_all(&dir).expect("Failed to create scratch directory");
+        let path = dir.join(name);
+        fs::write(&path, contents).expect("Failed to write scratch file");
+
+        let mut problems = Problems::default();
+        check(&path, &mut problems);
+        problems.0
+    }
+
+    fn sheet_prefab(definitions: &str) -> String {
+        format!(r#"Prefab(
+    entities: [
+        PrefabEntity(
+            data: (
+                sheet: Sheet(
+                    texture: File("textures/player.png", ("IMAGE", ())),
+                    sprites: [
+{}
+                    ],
+                ),
+            ),
+        ),
+    ],
+)
+"#, definitions)
+    }
+
+    const LIST: &str = "                        List((
+                            texture_width: 320,
+                            texture_height: 320,
+                            sprites: [
+                                (x: 0, y: 0, width: 32, height: 32),
+                            ],
+                        )),";
+
+    #[test]
+    fn tiles_past_the_tilesets_are_reported() {
+        let map = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="orthogonal" renderorder="left-up" width="2" height="1" tilewidth="32" tileheight="32" infinite="0" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="1" name="Map" tilewidth="32" tileheight="32" tilecount="100" columns="10">
+  <image source="{}" width="320" height="320"/>
+ </tileset>
+ <layer id="1" name="Tiles" width="2" height="1">
+  <data encoding="csv">
+100,101
+</data>
+ </layer>
+</map>
+"#, repo_path("assets/textures/terrain.png").display());
+
+        let problems = problems_in("gid_range.tmx", &map, validate_map);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, Some(8));
+        assert!(problems[0].message.contains("uses tile 101"));
+    }
+
+    #[test]
+    fn sprites_outside_the_texture_are_reported() {
+        let prefab = sheet_prefab(&LIST.replace(
+            "(x: 0, y: 0, width: 32, height: 32),",
+            "(x: 0, y: 0, width: 32, height: 32),\n                                (x: 300, y: 0, width: 32, height: 32),"
+        ));
+
+        let problems = problems_in("sprite_bounds.ron", &prefab, |path, problems| {
+            validate_prefab(path, &repo_path("assets"), problems)
+        });
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("sprite 1 (300, 0, 32x32) goes outside"));
+    }
+
+    #[test]
+    fn grid_and_list_in_one_sheet_are_reported() {
+        let grid = "                        Grid((
+                            texture_width: 320,
+                            texture_height: 320,
+                            columns: 10,
+                            rows: 10,
+                        )),";
+        let prefab = sheet_prefab(&format!("{}\n{}", grid, LIST));
+
+        let problems = problems_in("grid_and_list.ron", &prefab, |path, problems| {
+            validate_prefab(path, &repo_path("assets"), problems)
+        });
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("Grid on line 8 and List on line 14"));
+    }
+
+    #[test]
+    fn duplicate_widget_ids_are_reported() {
+        let ui = r#"#![enable(implicit_some)]
+Container(
+    transform: (id: "hud"),
+    children: [
+        Label(transform: (id: "hp_label")),
+        Label(transform: (id: "hp_label")),
+    ],
+)
+"#;
+
+        let problems = problems_in("duplicate_ids.ron", ui, |path, problems| {
+            validate_ui_prefab(path, &repo_path("assets"), problems)
+        });
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, Some(6));
+    }
+
+    #[test]
+    fn lists_are_read_from_after_the_opening_bracket() {
+        assert_eq!(list_after("output: [1, 2],", "output:"), Some(vec![1, 2]));
+        assert_eq!(list_after("output: ] [3]", "output:"), Some(vec![3]));
+        assert_eq!(list_after("output: ]", "output:"), None);
+    }
+}
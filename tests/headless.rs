@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+use amethyst::{
+    core::{
+        math::Vector2,
+        transform::Transform
+    },
+    ecs::prelude::{Entity, Join, WorldExt}
+};
+use ld45::{
+    collision_world::CollisionWorld,
+    components::{self, FoodType},
+    config::DeathConfig,
+    headless::{self, Simulation},
+    paths::GamePaths,
+    systems::death::Lives
+};
+
+fn game_paths() -> GamePaths {
+    GamePaths::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")))
+}
+
+fn player_position(simulation: &Simulation) -> Vector2<f32> {
+    let world = simulation.world();
+    let player_storage = world.read_storage::<components::Player>();
+    let transform_storage = world.read_storage::<Transform>();
+    (&player_storage, &transform_storage)
+        .join()
+        .map(|(_, transform)| Vector2::new(transform.translation().x, transform.translation().y))
+        .next()
+        .expect("No player in the level")
+}
+
+fn player_x(simulation: &Simulation) -> f32 {
+    player_position(simulation).x
+}
+
+// the player can only walk along the ground, so things placed up in the level get moved down into their way
+fn move_into_path(simulation: &mut Simulation, entity: Entity, x: f32) {
+    let y = player_position(simulation).y;
+    let world = simulation.world_mut();
+    let mut transform_storage = world.write_storage::<Transform>();
+    let collider_storage = world.read_storage::<components::Collider>();
+    let mut collision_world = world.write_resource::<CollisionWorld>();
+    collision_world.teleport(
+        transform_storage.get_mut(entity).expect("Entity has no transform"),
+        collider_storage.get(entity).expect("Entity has no collider"),
+        Vector2::new(x, y)
+    );
+}
+
+#[test]
+fn holding_right_moves_the_player_right() {
+    let mut simulation = Simulation::new(
+        &game_paths(),
+        &game_paths().resource("level1.ron"),
+        headless::hold(1.0, &[], 60)
+    )
+        .expect("Failed to start simulation");
+    let start_x = player_x(&simulation);
+
+    simulation.run(60);
+
+    assert!(player_x(&simulation) > start_x);
+}
+
+#[test]
+fn walking_into_food_and_an_enemy_changes_the_player() {
+    // 3.5 seconds, far enough to get past both but not long enough to digest anything
+    let mut simulation = Simulation::new(
+        &game_paths(),
+        &game_paths().resource("level1.ron"),
+        headless::hold(1.0, &[], 210)
+    )
+        .expect("Failed to start simulation");
+
+    let (blueberries, snake) = {
+        let world = simulation.world();
+        let entities = world.entities();
+        let food_storage = world.read_storage::<components::Food>();
+        let enemy_storage = world.read_storage::<components::Enemy>();
+        let blueberries = (&entities, &food_storage)
+            .join()
+            .find(|(_, food)| food.food_type() == FoodType::Blueberries)
+            .map(|(entity, _)| entity)
+            .expect("No blueberries in the level");
+        let snake = (&entities, &enemy_storage)
+            .join()
+            .map(|(entity, _)| entity)
+            .next()
+            .expect("No enemy in the level");
+        (blueberries, snake)
+    };
+    let start_x = player_x(&simulation);
+    move_into_path(&mut simulation, blueberries, start_x + 128.0);
+    move_into_path(&mut simulation, snake, start_x + 480.0);
+
+    simulation.run(210);
+
+    let world = simulation.world();
+    let player_storage = world.read_storage::<components::Player>();
+    let hp_storage = world.read_storage::<components::HP>();
+    let power_storage = world.read_storage::<components::Power>();
+    let (player, hp, power) = (&player_storage, &hp_storage, &power_storage)
+        .join()
+        .next()
+        .expect("No player in the level");
+
+    assert!(player_x(&simulation) > start_x + 480.0, "the player didn't get past the snake");
+    // blueberries fill one belly and give one power
+    assert_eq!(player.belly, player.belly_max() / 2 + 1);
+    assert_eq!(power.value, 2);
+    // the snake bites once, for 10
+    assert_eq!(hp.value, hp.max - 10);
+    assert_eq!(world.read_resource::<Lives>().remaining, world.read_resource::<DeathConfig>().lives);
+    assert!(world.read_storage::<components::Food>().get(blueberries).is_none());
+}
+
+#[test]
+fn time_passes_without_a_script() {
+    let mut simulation = Simulation::new(&game_paths(), &game_paths().resource("level1.ron"), Vec::new())
+        .expect("Failed to start simulation");
+
+    simulation.run(60);
+
+    let world = simulation.world();
+    let player_storage = world.read_storage::<components::Player>();
+    let player = player_storage.join().next().expect("No player in the level");
+    assert!(player.digestion > 0.9);
+}